@@ -7,10 +7,12 @@
 // Public License along with deadfish. If not, see http://www.gnu.org/licenses/.
 
 use std::cmp::Ordering;
+use std::collections::HashSet;
+use std::error::Error;
 use std::fmt::{self, Display, Formatter};
 use std::ops::{Add, AddAssign, Neg, Sub, SubAssign};
 
-use crate::Inst;
+use crate::{heuristic_len, Inst};
 
 #[repr(transparent)]
 #[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
@@ -21,6 +23,49 @@ pub struct Acc(u32);
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub struct Offset(pub i64);
 
+/// An error for when an instruction reaches 256 or -1 and resets the
+/// accumulator to 0, as reported by [`Acc::try_apply`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ResetFired {
+    /// Whether it reset from 256, rather than from -1.
+    pub from_256: bool,
+}
+
+/// Integer width of the accumulator in bits. The reference implementation uses
+/// a 32-bit `int`, but ports exist with other widths. The accumulator is still
+/// reset at 256 and -1, but arithmetic wraps at the width.
+#[repr(transparent)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Width(u32);
+
+/// An accumulator type that the encoders are generic over. The encoders do
+/// their arithmetic on an [`Acc`] at a [`Width`], so an implementation only
+/// converts to and from `Acc` and gives its width.
+pub trait Accumulator: Copy {
+    /// The width of the arithmetic.
+    fn width() -> Width;
+
+    /// Converts to an [`Acc`] of the same value.
+    fn to_acc(self) -> Acc;
+
+    /// Converts from an [`Acc`], which is valid at [`Accumulator::width`].
+    fn from_acc(acc: Acc) -> Self;
+
+    /// Compute the operation on the accumulator at its width.
+    #[must_use]
+    #[inline]
+    fn apply(self, inst: Inst) -> Self {
+        Self::from_acc(Self::width().apply(self.to_acc(), inst))
+    }
+
+    /// Computes the offset to `other`, if it doesn't cross 256.
+    #[must_use]
+    #[inline]
+    fn offset_to(self, other: Self) -> Option<Offset> {
+        self.to_acc().offset_to(other.to_acc())
+    }
+}
+
 impl Acc {
     /// Create a new accumulator at zero.
     #[must_use]
@@ -51,6 +96,37 @@ impl Acc {
         self.0
     }
 
+    /// Yields every valid accumulator in unsigned order, i.e., 0 through
+    /// `u32::MAX - 1`, except 256, since 256 and -1 reset to 0.
+    #[inline]
+    pub fn all() -> impl Iterator<Item = Acc> {
+        (0..u32::MAX).filter(|&n| n != 256).map(Acc)
+    }
+
+    /// Computes the number of bytes in the signed decimal representation.
+    #[must_use]
+    pub const fn decimal_len(self) -> usize {
+        let n = self.0 as i32;
+        let mut len = if n < 0 { 2 } else { 1 };
+        let mut n = n.unsigned_abs();
+        while n >= 10 {
+            n /= 10;
+            len += 1;
+        }
+        len
+    }
+
+    /// Formats the value like `Display`, but notes that 0 may have been reset
+    /// from 256 or -1, since its origin is ambiguous.
+    #[must_use]
+    pub fn describe(self) -> String {
+        if self.0 == 0 {
+            "0 (or reset from 256/-1)".to_owned()
+        } else {
+            self.to_string()
+        }
+    }
+
     /// Compute the operation on the accumulator.
     #[must_use]
     #[inline]
@@ -63,6 +139,53 @@ impl Acc {
         }
     }
 
+    /// Compute the operation on the accumulator and whether it reset to 0
+    /// from 256 or -1.
+    #[must_use]
+    #[inline]
+    pub const fn apply_checked(self, inst: Inst) -> (Self, bool) {
+        let n = self.apply_raw(inst);
+        let acc = normalize(n);
+        (Acc(acc), acc != n)
+    }
+
+    /// Compute the operation on the accumulator, but fail when it resets to 0
+    /// from 256 or -1, to distinguish it from reaching 0 without a reset.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ResetFired`], if the operation produces 256 or -1.
+    #[inline]
+    pub const fn try_apply(self, inst: Inst) -> Result<Self, ResetFired> {
+        match self.apply_raw(inst) {
+            256 => Err(ResetFired { from_256: true }),
+            u32::MAX => Err(ResetFired { from_256: false }),
+            n => Ok(Acc(n)),
+        }
+    }
+
+    /// Compute the operation on the accumulator, before 256 and -1 are reset
+    /// to 0, as in dialects that do not reset.
+    #[must_use]
+    #[inline]
+    pub const fn apply_raw(self, inst: Inst) -> u32 {
+        match inst {
+            Inst::I => self.0.wrapping_add(1),
+            Inst::D => self.0.wrapping_sub(1),
+            Inst::S => self.0.wrapping_mul(self.0),
+            _ => self.0,
+        }
+    }
+
+    /// Computes every accumulator that squares to this value, excluding the
+    /// roots of 256 and -1 for non-zero values and roots that are themselves
+    /// 256 or -1. These are the predecessors of `s` for a backward search.
+    #[must_use]
+    #[inline]
+    pub fn wrapping_sqrts_in_domain(self) -> Vec<Acc> {
+        Width::default().wrapping_sqrts_in_domain(self)
+    }
+
     /// Compute the inverse operation on the accumulator, if possible.
     #[must_use]
     #[inline]
@@ -127,13 +250,33 @@ impl Acc {
         }
     }
 
+    /// Computes the values of squaring `count` times, starting with this
+    /// value, so it has `count + 1` values. Once it resets to 0, it stays 0,
+    /// which is where `square_repeat` stops early.
+    #[must_use]
+    pub fn square_orbit(self, count: u32) -> Vec<Acc> {
+        let mut orbit = Vec::with_capacity(count as usize + 1);
+        let mut acc = self;
+        orbit.push(acc);
+        for _ in 0..count {
+            acc = acc.square();
+            orbit.push(acc);
+        }
+        orbit
+    }
+
+    /// Squares the accumulator `count` times. Squaring reaches a fixed point
+    /// quickly, so it stops there, regardless of `count`: an even value has
+    /// its trailing zeros doubled until it's 0 within 5 squares, and an odd
+    /// value `x` has `x^(2^30)` equal to 1 modulo `2^32`, so it's 1 within 30
+    /// squares.
     #[must_use]
     #[inline]
     pub fn square_repeat(self, count: u32) -> Self {
         let mut n = self.0;
         for _ in 0..count {
             n = normalize(n.wrapping_mul(n));
-            if n == 0 {
+            if n == 0 || n == 1 {
                 break;
             }
         }
@@ -143,17 +286,25 @@ impl Acc {
     #[must_use]
     #[inline]
     pub fn nearest_sqrt(&self) -> (Acc, Offset) {
-        let sqrt = (self.0 as f64).sqrt();
-        let floor = sqrt.floor() as u32;
-        let ceil = sqrt.ceil() as u32;
-        let floor_diff = self.0 - floor * floor;
-        let ceil_diff = ceil * ceil - self.0;
-        // Choose the closer square root and avoid squaring to 256 or 1 << 32
-        if floor_diff < ceil_diff && floor != 16 || ceil == 16 || ceil == 65536 {
-            (Acc::from_raw(floor), Offset(floor_diff as i64))
-        } else {
-            (Acc::from_raw(ceil), Offset(-(ceil_diff as i64)))
+        Width::default().nearest_sqrt(*self)
+    }
+
+    /// Finds the deepest common value in the square chains of both values,
+    /// as constructed by the heuristic encoder, from which both can be
+    /// reached by squares and offsets. Each chain repeatedly takes the nearest
+    /// root until it's below 4. Returns `None`, if the chains don't meet.
+    #[must_use]
+    pub fn shared_square_prefix(self, other: Acc) -> Option<Acc> {
+        fn chain(mut n: Acc) -> Vec<Acc> {
+            let mut chain = vec![n];
+            while n >= 4 {
+                n = n.nearest_sqrt().0;
+                chain.push(n);
+            }
+            chain
         }
+        let other_chain = chain(other);
+        chain(self).into_iter().find(|n| other_chain.contains(n))
     }
 
     #[must_use]
@@ -165,6 +316,39 @@ impl Acc {
             None
         }
     }
+
+    /// Computes the fewest squares in any program from 0 to this value. Every
+    /// value below 256 is reached by increments alone and every value above by
+    /// squaring 17 to 289, then offsetting, so it's 0 or 1. This is unrelated
+    /// to the number of squares in the shortest program, e.g., 16 is `iiss`.
+    #[must_use]
+    #[inline]
+    pub const fn min_squares_from_zero(self) -> u32 {
+        u32::from(self.0 >= 256)
+    }
+
+    /// Computes the number of instructions the heuristic encoder uses to
+    /// reach `other`, i.e., the shortest of a direct offset, via 0, and via a
+    /// root of `other`. It's not a true metric: it's not symmetric and the
+    /// triangle inequality may not hold, because of squares.
+    #[must_use]
+    pub fn heuristic_distance(self, other: Acc) -> u32 {
+        heuristic_len(self, other, Width::default()) as u32
+    }
+
+    /// Chooses the single instruction that makes the most progress toward
+    /// `target`, by the heuristic distance from each resulting value,
+    /// preferring `i`, then `d`, then `s` for ties. Returns `None`, if already
+    /// at `target`. Following it greedily is not always optimal.
+    #[must_use]
+    pub fn step_toward(self, target: Acc) -> Option<Inst> {
+        if self == target {
+            return None;
+        }
+        [Inst::I, Inst::D, Inst::S]
+            .into_iter()
+            .min_by_key(|&inst| self.apply(inst).heuristic_distance(target))
+    }
 }
 
 impl Offset {
@@ -178,6 +362,33 @@ impl Offset {
         }
     }
 
+    /// Computes the offset from `from` to `to`, if it doesn't cross 256. The
+    /// same as [`Acc::offset_to`].
+    #[must_use]
+    #[inline]
+    pub const fn between(from: Acc, to: Acc) -> Option<Self> {
+        from.offset_to(to)
+    }
+
+    /// Applies the offset to `acc`, if it reaches its value exactly, without
+    /// crossing 256 or -1 and resetting.
+    #[must_use]
+    #[inline]
+    pub const fn checked_apply(self, acc: Acc) -> Option<Acc> {
+        const MAX: i64 = u32::MAX as i64;
+        let n = acc.0 as i64 + self.0;
+        let in_range = if acc.0 < 256 {
+            matches!(n, 0..256)
+        } else {
+            matches!(n, 257..MAX)
+        };
+        if in_range {
+            Some(Acc(n as u32))
+        } else {
+            None
+        }
+    }
+
     #[must_use]
     #[inline]
     pub const fn abs(&self) -> u32 {
@@ -197,6 +408,215 @@ impl Offset {
     }
 }
 
+impl Width {
+    /// Creates a width of `bits`, which must be 9 to 32. A width of 8 bits
+    /// can't represent 256, so the reset at 256 is indistinguishable from
+    /// wrapping, and widths above 32 bits don't fit in an [`Acc`].
+    #[must_use]
+    #[inline]
+    pub const fn new(bits: u32) -> Option<Self> {
+        if matches!(bits, 9..=32) {
+            Some(Width(bits))
+        } else {
+            None
+        }
+    }
+
+    #[must_use]
+    #[inline]
+    pub const fn bits(self) -> u32 {
+        self.0
+    }
+
+    /// The mask of valid bits, which is also the value of -1.
+    #[must_use]
+    #[inline]
+    pub const fn mask(self) -> u32 {
+        u32::MAX >> (32 - self.0)
+    }
+
+    /// Wraps `n` to the width and resets 256 and -1 to 0.
+    #[must_use]
+    #[inline]
+    pub const fn normalize(self, n: u32) -> u32 {
+        let n = n & self.mask();
+        if n == 256 || n == self.mask() {
+            0
+        } else {
+            n
+        }
+    }
+
+    /// Compute the operation on the accumulator.
+    #[must_use]
+    #[inline]
+    pub const fn apply(self, acc: Acc, inst: Inst) -> Acc {
+        let n = acc.value();
+        Acc::from_raw(match inst {
+            Inst::I => self.normalize(n.wrapping_add(1)),
+            Inst::D => self.normalize(n.wrapping_sub(1)),
+            Inst::S => self.normalize(n.wrapping_mul(n)),
+            _ => n,
+        })
+    }
+
+    #[must_use]
+    #[inline]
+    pub fn eval(self, insts: &[Inst], acc: Acc) -> Acc {
+        insts.iter().fold(acc, |acc, &inst| self.apply(acc, inst))
+    }
+
+    /// Adds `x` to the accumulator, like [`Acc`]'s `Add`, but at this width.
+    #[must_use]
+    #[inline]
+    pub const fn add(self, acc: Acc, x: u32) -> Acc {
+        let n = acc.value();
+        let add = n.saturating_add(x);
+        if n < 256 && add >= 256 || add >= self.mask() {
+            Acc::new()
+        } else {
+            Acc::from_raw(add)
+        }
+    }
+
+    /// Subtracts `x` from the accumulator, like [`Acc`]'s `Sub`, but at this
+    /// width.
+    #[must_use]
+    #[inline]
+    pub const fn sub(self, acc: Acc, x: u32) -> Acc {
+        let n = acc.value();
+        let sub = n.saturating_sub(x);
+        if n > 256 && sub <= 256 {
+            Acc::new()
+        } else {
+            Acc::from_raw(sub)
+        }
+    }
+
+    #[must_use]
+    #[inline]
+    pub const fn offset(self, acc: Acc, offset: Offset) -> Acc {
+        if offset.is_negative() {
+            self.sub(acc, offset.abs())
+        } else {
+            self.add(acc, offset.abs())
+        }
+    }
+
+    /// Squares the accumulator `count` times, like [`Acc::square_repeat`],
+    /// stopping once it reaches the fixed point of 0 or 1.
+    #[must_use]
+    #[inline]
+    pub fn square_repeat(self, acc: Acc, count: u32) -> Acc {
+        let mut acc = acc;
+        for _ in 0..count {
+            acc = self.apply(acc, Inst::S);
+            if acc == 0 || acc == 1 {
+                break;
+            }
+        }
+        acc
+    }
+
+    /// Computes every value reachable from `start` by `i`, `d`, and `s`, by
+    /// expanding until no new values appear. Since all values above 256 are
+    /// connected by offsets, this is at worst every value other than 256 and
+    /// -1, i.e., `2^bits - 2` values, so it's only practical for small widths.
+    #[must_use]
+    pub fn reachable_closure(self, start: Acc) -> HashSet<Acc> {
+        let mut visited = HashSet::new();
+        let mut stack = vec![start];
+        visited.insert(start);
+        while let Some(acc) = stack.pop() {
+            for inst in [Inst::I, Inst::D, Inst::S] {
+                let next = self.apply(acc, inst);
+                if visited.insert(next) {
+                    stack.push(next);
+                }
+            }
+        }
+        visited
+    }
+
+    /// Computes every accumulator that squares to `acc` at this width. When
+    /// `acc` is 0, this includes the roots of 256 and -1, since those are
+    /// reset.
+    #[must_use]
+    pub fn wrapping_sqrts_in_domain(self, acc: Acc) -> Vec<Acc> {
+        let mut roots = self.wrapping_sqrts(acc.value());
+        if acc == 0 {
+            roots.extend(self.wrapping_sqrts(256));
+            roots.extend(self.wrapping_sqrts(self.mask()));
+        }
+        let mut roots = roots
+            .into_iter()
+            .filter(|&x| self.normalize(x) == x)
+            .map(Acc::from_raw)
+            .collect::<Vec<_>>();
+        roots.sort_unstable();
+        roots
+    }
+
+    /// Computes every `x` where `x * x` is `n` modulo `2^bits`. Roots are
+    /// lifted one bit at a time, since a root modulo `2^k` is also a root
+    /// modulo `2^(k-1)`.
+    fn wrapping_sqrts(self, n: u32) -> Vec<u32> {
+        let mut roots = vec![0u32];
+        for k in 1..=self.0 {
+            let mask = u32::MAX >> (32 - k);
+            let bit = 1 << (k - 1);
+            roots = roots
+                .into_iter()
+                .flat_map(|r| [r, r | bit])
+                .filter(|&x| x.wrapping_mul(x) & mask == n & mask)
+                .collect();
+        }
+        roots
+    }
+
+    /// Finds the nearest root that can be squared and offset to `acc`. The
+    /// root is a valid accumulator and does not square to 256 or overflow.
+    #[must_use]
+    pub fn nearest_sqrt(self, acc: Acc) -> (Acc, Offset) {
+        let n = acc.value();
+        let sqrt = (n as f64).sqrt();
+        let mut floor = sqrt.floor() as u32;
+        let mut ceil = sqrt.ceil() as u32;
+        // Avoid 256 as a root and squaring to 256
+        if floor == 16 || floor == 256 {
+            floor -= 1;
+        }
+        if ceil == 16 || ceil == 256 {
+            ceil += 1;
+        }
+        let floor_diff = n - floor * floor;
+        // Square in 64 bits, since `ceil` may overflow the width
+        let ceil_square = ceil as u64 * ceil as u64;
+        if floor_diff < (ceil_square - n as u64) as u32 || ceil_square >= self.mask() as u64 {
+            (Acc::from_raw(floor), Offset(floor_diff as i64))
+        } else {
+            (Acc::from_raw(ceil), Offset(n as i64 - ceil_square as i64))
+        }
+    }
+}
+
+impl Accumulator for Acc {
+    #[inline]
+    fn width() -> Width {
+        Width::default()
+    }
+
+    #[inline]
+    fn to_acc(self) -> Acc {
+        self
+    }
+
+    #[inline]
+    fn from_acc(acc: Acc) -> Self {
+        acc
+    }
+}
+
 impl const Add<u32> for Acc {
     type Output = Acc;
 
@@ -321,6 +741,13 @@ impl const Default for Acc {
     }
 }
 
+impl const Default for Width {
+    #[inline]
+    fn default() -> Self {
+        Width(32)
+    }
+}
+
 impl const From<u32> for Acc {
     #[inline]
     fn from(n: u32) -> Self {
@@ -356,12 +783,28 @@ impl const From<i64> for Offset {
     }
 }
 
+/// Formats the accumulator as signed, like Deadfish prints it, or as the raw
+/// unsigned value with the alternate flag (`{:#}`), which is clearer when
+/// reasoning about wrapping arithmetic.
 impl Display for Acc {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        write!(f, "{}", self.0 as i32)
+        if f.alternate() {
+            write!(f, "{}", self.0)
+        } else {
+            write!(f, "{}", self.0 as i32)
+        }
     }
 }
 
+impl Display for ResetFired {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let from = if self.from_256 { 256 } else { -1 };
+        write!(f, "accumulator reset to 0 from {from}")
+    }
+}
+
+impl Error for ResetFired {}
+
 impl Display for Offset {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         write!(f, "{}", self.0)
@@ -6,11 +6,13 @@
 // later version. You should have received a copy of the GNU Lesser General
 // Public License along with deadfish. If not, see http://www.gnu.org/licenses/.
 
-use std::collections::{HashSet, VecDeque};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt::{self, Display, Formatter};
+use std::ops::RangeInclusive;
 
 use fxhash::FxBuildHasher;
 
-use crate::{heuristic_encode, Acc, Builder, Inst};
+use crate::{heuristic_encode, heuristic_len, Acc, Accumulator, Builder, Inst, Width};
 
 #[derive(Clone, Debug)]
 pub struct BfsEncoder {
@@ -18,6 +20,32 @@ pub struct BfsEncoder {
     index: usize,
     visited: HashSet<Acc, FxBuildHasher>,
     max_len: u16,
+    width: Width,
+}
+
+/// Statistics of a search by [`BfsEncoder`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct SearchStats {
+    /// Number of distinct values visited.
+    pub nodes_visited: usize,
+    /// Maximum number of nodes queued, but not yet expanded.
+    pub max_frontier: usize,
+    /// Whether the path is known to be optimal.
+    pub optimal: bool,
+}
+
+/// Tallies how the heuristic encoder compares to optimal paths found by a
+/// breadth-first search, as reported by [`compare_encoders_report`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct EncoderReport {
+    /// Number of `(acc, n)` pairs compared.
+    pub pairs: usize,
+    /// Number of pairs, where the heuristic is optimal.
+    pub matched: usize,
+    /// Number of pairs, where the heuristic is longer than optimal.
+    pub longer: usize,
+    /// Maximum number of instructions the heuristic is longer by.
+    pub max_excess: usize,
 }
 
 /// `Node` is a linked list element in a search path. It contains the
@@ -53,6 +81,7 @@ impl BfsEncoder {
             index: 0,
             visited: HashSet::default(),
             max_len: max_len.try_into().unwrap_or(u16::MAX),
+            width: Width::default(),
         }
     }
 
@@ -61,16 +90,50 @@ impl BfsEncoder {
         self.max_len = max_len.try_into().unwrap_or(u16::MAX);
     }
 
+    /// Sets the width of the accumulator to encode for.
+    #[inline]
+    pub fn set_width(&mut self, width: Width) {
+        self.width = width;
+    }
+
     /// Performs a breadth-first search to encode `n` as Deadfish instructions.
     /// Returns a path, if one could be constructed, and whether it's optimal.
     #[must_use]
     pub fn encode(&mut self, acc: Acc, n: Acc) -> (Option<Vec<Inst>>, bool) {
+        let (path, optimal, _) = self.encode_with_stats(acc, n);
+        (path, optimal)
+    }
+
+    /// Performs a breadth-first search like [`BfsEncoder::encode`] for any
+    /// [`Accumulator`], with arithmetic at its width.
+    #[must_use]
+    pub fn encode_accumulator<A: Accumulator>(
+        &mut self,
+        acc: A,
+        n: A,
+    ) -> (Option<Vec<Inst>>, bool) {
+        let width = self.width;
+        self.set_width(A::width());
+        let path = self.encode(acc.to_acc(), n.to_acc());
+        self.width = width;
+        path
+    }
+
+    /// Performs a breadth-first search like [`BfsEncoder::encode`] and also
+    /// reports statistics of the search, to help choose a bound.
+    #[must_use]
+    pub fn encode_with_stats(
+        &mut self,
+        acc: Acc,
+        n: Acc,
+    ) -> (Option<Vec<Inst>>, bool, SearchStats) {
         self.queue.clear();
         self.index = 0;
         self.visited.clear();
 
         let mut zero_index = None;
         let mut closest_square = None;
+        let mut max_frontier = 1;
 
         self.queue.push(Node {
             acc,
@@ -80,7 +143,8 @@ impl BfsEncoder {
         });
         while let Some((i, node)) = self.queue_next() {
             if node.acc == n {
-                return (Some(self.path_from_queue(i)), true);
+                let stats = self.stats(max_frontier, true);
+                return (Some(self.path_from_queue(i)), true, stats);
             }
 
             // Track the shortest path to 0, because a path from 0 to `n` is
@@ -90,8 +154,10 @@ impl BfsEncoder {
             }
 
             if node.len < self.max_len {
+                // Expand in `Inst` order, so that nodes at each depth are
+                // queued in lexicographic order of their paths
                 for inst in [Inst::I, Inst::D, Inst::S] {
-                    let acc = node.acc.apply(inst);
+                    let acc = self.width.apply(node.acc, inst);
                     if self.visited.insert(acc) {
                         let path_len = node.len + 1;
                         self.queue.push(Node {
@@ -100,7 +166,7 @@ impl BfsEncoder {
                             prev: i,
                             len: path_len,
                         });
-                        let i = self.queue.len();
+                        let i = self.queue.len() - 1;
 
                         // Track the square that is closest to `n` by an offset
                         if inst == Inst::S {
@@ -113,24 +179,77 @@ impl BfsEncoder {
                         }
                     }
                 }
+                max_frontier = max_frontier.max(self.queue.len() - self.index);
             }
         }
 
+        let stats = self.stats(max_frontier, false);
         let mut path = None;
         if let Some(i) = zero_index {
-            let mut b = Builder::from_insts(self.path_from_queue(i), Acc::new());
+            let mut b =
+                Builder::from_insts_with_width(self.path_from_queue(i), Acc::new(), self.width);
             heuristic_encode(&mut b, n);
             path = Some(b.into_insts());
         }
         if let Some((i, offset, _)) = closest_square {
-            let mut b = Builder::from_insts(self.path_from_queue(i), self.queue[i].acc);
+            let mut b = Builder::from_insts_with_width(
+                self.path_from_queue(i),
+                self.queue[i].acc,
+                self.width,
+            );
             b.offset(offset);
             let square_path = b.into_insts();
             if !matches!(&path, Some(path) if path.len() <= square_path.len()) {
                 path = Some(square_path);
             }
         }
-        (path, false)
+        (path, false, stats)
+    }
+
+    /// Performs a breadth-first search to encode `n` as Deadfish instructions
+    /// with at most `max_len` instructions. Unlike [`BfsEncoder::encode`], it
+    /// never falls back to a longer heuristic path.
+    #[must_use]
+    pub fn encode_within(&mut self, acc: Acc, n: Acc, max_len: usize) -> Option<Vec<Inst>> {
+        let bound = self.max_len;
+        self.set_bound(max_len);
+        let (path, optimal) = self.encode(acc, n);
+        self.max_len = bound;
+        path.filter(|_| optimal)
+    }
+
+    /// Performs a breadth-first search for the lexicographically smallest
+    /// optimal path to encode `n`, by the ordering `i < d < s`. Since the
+    /// first path found at each depth is the smallest, the result does not
+    /// depend on which optimal path other encoders choose. Every path found by
+    /// the search is canonical, so this is [`BfsEncoder::encode_within`] at the
+    /// current bound.
+    #[must_use]
+    #[inline]
+    pub fn encode_canonical(&mut self, acc: Acc, n: Acc) -> Option<Vec<Inst>> {
+        self.encode_within(acc, n, self.max_len.into())
+    }
+
+    /// Finds the first instruction on an optimal path to encode `n`, as a
+    /// policy for a table-driven encoder. Since every suffix of an optimal
+    /// path is optimal, following it step by step reconstructs an optimal
+    /// path without storing it. Returns `None`, if `acc` is already `n` or no
+    /// path is found within the bound.
+    #[must_use]
+    pub fn optimal_first_step(&mut self, acc: Acc, n: Acc) -> Option<Inst> {
+        match self.encode(acc, n) {
+            (Some(path), true) => path.first().copied(),
+            _ => None,
+        }
+    }
+
+    #[inline]
+    fn stats(&self, max_frontier: usize, optimal: bool) -> SearchStats {
+        SearchStats {
+            nodes_visited: self.visited.len(),
+            max_frontier,
+            optimal,
+        }
     }
 
     #[inline]
@@ -161,8 +280,151 @@ impl BfsEncoder {
     }
 }
 
+/// Checks that the heuristic encoding of `acc` to `n` is no longer than the
+/// path found by a breadth-first search bounded by `max_len`.
+///
+/// # Errors
+///
+/// Returns the heuristic and the search paths, both ending with `o`, if the
+/// search finds a shorter path.
+pub fn heuristic_is_optimal(
+    acc: Acc,
+    n: Acc,
+    max_len: usize,
+) -> Result<(), (Vec<Inst>, Vec<Inst>)> {
+    let heuristic = Inst::encode_number(acc, n);
+    match BfsEncoder::with_bound(max_len).encode(acc, n).0 {
+        Some(mut bfs) if bfs.len() + 1 < heuristic.len() => {
+            bfs.push(Inst::O);
+            Err((heuristic, bfs))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Compares the heuristic encoder to optimal paths for every pair of values in
+/// the ranges.
+#[must_use]
+pub fn compare_encoders_report(
+    from_range: RangeInclusive<i32>,
+    to_range: RangeInclusive<i32>,
+) -> EncoderReport {
+    let mut enc = BfsEncoder::new();
+    let mut report = EncoderReport::default();
+    for acc in from_range.map(Acc::from) {
+        for n in to_range.clone().map(Acc::from) {
+            report.pairs += 1;
+            let heuristic = heuristic_len(acc, n, Width::default());
+            let optimal = optimal_len(&mut enc, acc, n);
+            if optimal < heuristic {
+                report.longer += 1;
+                report.max_excess = report.max_excess.max(heuristic - optimal);
+            } else {
+                report.matched += 1;
+            }
+        }
+    }
+    report
+}
+
+/// Computes the length of the shortest program that prints the bytes as
+/// numbers, starting from 0, by summing the optimal transitions between
+/// consecutive bytes and an `o` for each. Each distinct transition is
+/// searched only once. This is a baseline for comparing encoders, but the
+/// search is exponential in the length of the transitions.
+#[must_use]
+pub fn min_len_for_bytes(bytes: &[u8]) -> usize {
+    let mut enc = BfsEncoder::new();
+    let mut cache = HashMap::<_, _, FxBuildHasher>::default();
+    let mut len = 0;
+    let mut acc = Acc::new();
+    for &b in bytes {
+        let n = Acc::from(b as u32);
+        len += *cache
+            .entry((acc, n))
+            .or_insert_with(|| optimal_len(&mut enc, acc, n))
+            + 1;
+        acc = n;
+    }
+    len
+}
+
+/// Computes the length of the shortest path from `acc` to `n`, excluding the
+/// `o`. The search is bounded to one shorter than the heuristic, since, when
+/// nothing is found, the heuristic is optimal.
+fn optimal_len(enc: &mut BfsEncoder, acc: Acc, n: Acc) -> usize {
+    let heuristic = heuristic_len(acc, n, Width::default());
+    heuristic
+        .checked_sub(1)
+        .and_then(|bound| enc.encode_within(acc, n, bound))
+        .map_or(heuristic, |path| path.len())
+}
+
+/// Performs a breadth-first search over programs of at most `max_len`
+/// instructions for the shortest one, whose printed numbers satisfy
+/// `predicate`. Programs with the same accumulator and output are expanded
+/// only once, but the search is still exponential in `max_len`, so it's only
+/// practical for short programs.
+pub fn shortest_for_predicate<F: Fn(&[Acc]) -> bool>(
+    max_len: usize,
+    predicate: F,
+) -> Option<Vec<Inst>> {
+    let mut queue = VecDeque::new();
+    let mut visited = HashSet::<_, FxBuildHasher>::default();
+    queue.push_back((Vec::new(), Acc::new(), Vec::new()));
+    visited.insert((Acc::new(), Vec::new()));
+    while let Some((path, acc, output)) = queue.pop_front() {
+        if predicate(&output) {
+            return Some(path);
+        }
+        if path.len() < max_len {
+            for inst in [Inst::I, Inst::D, Inst::S, Inst::O] {
+                let mut output = output.clone();
+                let acc = if inst == Inst::O {
+                    output.push(acc);
+                    acc
+                } else {
+                    acc.apply(inst)
+                };
+                if visited.insert((acc, output.clone())) {
+                    let mut path = path.clone();
+                    path.push(inst);
+                    queue.push_back((path, acc, output));
+                }
+            }
+        }
+    }
+    None
+}
+
+impl Display for EncoderReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} pairs: {} optimal, {} longer by at most {}",
+            self.pairs, self.matched, self.longer, self.max_excess,
+        )
+    }
+}
+
 impl Default for BfsEncoder {
     fn default() -> Self {
         Self::new()
     }
 }
+
+#[test]
+fn encode_with_stats() {
+    let mut enc = BfsEncoder::with_bound(8);
+    let (path, optimal, stats) = enc.encode_with_stats(Acc::new(), Acc::from(16));
+    assert_eq!(Some(vec![Inst::I, Inst::I, Inst::S, Inst::S]), path);
+    assert!(optimal && stats.optimal);
+    assert_eq!(enc.visited.len(), stats.nodes_visited);
+    assert!(stats.max_frontier <= stats.nodes_visited);
+
+    enc.set_bound(2);
+    let (_, optimal, tight) = enc.encode_with_stats(Acc::new(), Acc::from(16));
+    assert!(!optimal && !tight.optimal);
+    assert_eq!(enc.visited.len(), tight.nodes_visited);
+    assert!(tight.nodes_visited < stats.nodes_visited);
+}
@@ -7,13 +7,43 @@
 // Public License along with deadfish. If not, see http://www.gnu.org/licenses/.
 
 use std::collections::VecDeque;
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+use std::iter;
 
-use crate::{heuristic_encode, Acc, Inst, Offset};
+use crate::{
+    fast_encode, heuristic_encode, heuristic_len_with_config, Acc, Accumulator, HeuristicConfig,
+    Inst, Offset, Width,
+};
 
 #[derive(Clone, Debug)]
 pub struct Builder {
     insts: Vec<Inst>,
     acc: Acc,
+    width: Width,
+    limit: usize,
+    config: HeuristicConfig,
+}
+
+/// An error for when a push would exceed the instruction limit of a
+/// [`Builder`]. The builder is left unchanged.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct LimitExceeded {
+    /// Number of instructions the push would produce.
+    pub len: usize,
+    /// Maximum number of instructions.
+    pub limit: usize,
+}
+
+/// An error for when [`Builder::push_number_via`] can't offset from the
+/// squared pivot to the number, because it would cross 256. The builder is
+/// left unchanged.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct PivotUnreachable {
+    /// The square of the pivot.
+    pub square: Acc,
+    /// The number to encode.
+    pub n: Acc,
 }
 
 impl Builder {
@@ -26,7 +56,64 @@ impl Builder {
     #[must_use]
     #[inline]
     pub fn from_insts(insts: Vec<Inst>, acc: Acc) -> Self {
-        Builder { insts, acc }
+        Self::from_insts_with_width(insts, acc, Width::default())
+    }
+
+    /// Creates a builder, which encodes for an accumulator of the given width.
+    #[must_use]
+    #[inline]
+    pub fn with_width(acc: Acc, width: Width) -> Self {
+        Self::from_insts_with_width(Vec::new(), acc, width)
+    }
+
+    /// Creates a builder, which encodes for any [`Accumulator`] at its width.
+    #[must_use]
+    #[inline]
+    pub fn from_accumulator<A: Accumulator>(acc: A) -> Self {
+        Self::with_width(acc.to_acc(), A::width())
+    }
+
+    #[must_use]
+    #[inline]
+    pub fn from_insts_with_width(insts: Vec<Inst>, acc: Acc, width: Width) -> Self {
+        Builder {
+            insts,
+            acc,
+            width,
+            limit: usize::MAX,
+            config: HeuristicConfig::default(),
+        }
+    }
+
+    /// Creates a builder, where the checked pushes refuse to produce more than
+    /// `max_insts` instructions in total. The unchecked pushes ignore it.
+    #[must_use]
+    #[inline]
+    pub fn with_limit(acc: Acc, max_insts: usize) -> Self {
+        let mut b = Self::new(acc);
+        b.limit = max_insts;
+        b
+    }
+
+    #[must_use]
+    #[inline]
+    pub const fn limit(&self) -> usize {
+        self.limit
+    }
+
+    /// Creates a builder, which tunes the heuristic encoder with `config`.
+    #[must_use]
+    #[inline]
+    pub fn with_heuristic_config(acc: Acc, config: HeuristicConfig) -> Self {
+        let mut b = Self::new(acc);
+        b.config = config;
+        b
+    }
+
+    #[must_use]
+    #[inline]
+    pub const fn heuristic_config(&self) -> HeuristicConfig {
+        self.config
     }
 
     #[must_use]
@@ -35,6 +122,12 @@ impl Builder {
         self.acc
     }
 
+    #[must_use]
+    #[inline]
+    pub const fn width(&self) -> Width {
+        self.width
+    }
+
     #[must_use]
     #[inline]
     pub fn insts(&self) -> &[Inst] {
@@ -56,28 +149,159 @@ impl Builder {
     /// Encodes `n` as Deadfish instructions.
     #[inline]
     pub fn push_number(&mut self, n: Acc) {
+        self.goto(n);
+        self.insts.push(Inst::O);
+    }
+
+    /// Moves the accumulator to `n` by the heuristic encoding, like
+    /// [`Builder::push_number`], but without printing it.
+    #[inline]
+    pub fn goto(&mut self, n: Acc) {
         heuristic_encode(self, n);
+        self.acc = n;
+    }
+
+    /// Encodes `n` without searching or comparing routes, for encoding
+    /// high-throughput streams. It offsets directly, when possible, and
+    /// otherwise resets to 0 and squares up to `n`. The result may be much
+    /// longer than [`Builder::push_number`], particularly when a direct offset
+    /// is long or when `acc` is near a root of `n`.
+    #[inline]
+    pub fn push_number_fast(&mut self, n: Acc) {
+        fast_encode(self, n);
         self.insts.push(Inst::O);
         self.acc = n;
     }
 
+    /// Encodes `n` through a chosen pivot, e.g., to demonstrate a route: it
+    /// moves to `pivot` by the heuristic, squares it, and offsets to `n`.
+    /// Every pivot is reachable, but `n` must be on the same side of 256 as the
+    /// square of `pivot`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`PivotUnreachable`] and leaves the builder unchanged, if `n` is
+    /// on the other side of 256 from the square of `pivot`.
+    pub fn push_number_via(&mut self, pivot: Acc, n: Acc) -> Result<(), PivotUnreachable> {
+        let square = self.width.apply(pivot, Inst::S);
+        let offset = square.offset_to(n).ok_or(PivotUnreachable { square, n })?;
+        self.goto(pivot);
+        self.push(Inst::S);
+        self.offset(offset);
+        self.insts.push(Inst::O);
+        Ok(())
+    }
+
+    /// Encodes `n` by approaching it from above with decrements, instead of
+    /// the heuristic's preferred route. It squares the ceiling root of `n`,
+    /// or, when that square would reset, offsets to the greatest value on the
+    /// same side of 256.
+    pub fn push_number_from_above(&mut self, n: Acc) {
+        let mut ceil = (n.value() as f64).sqrt().ceil() as u32;
+        if ceil == 256 {
+            ceil += 1;
+        }
+        let square = ceil as u64 * ceil as u64;
+        let (max, root) = if n < 256 {
+            (255, square < 256)
+        } else {
+            (self.width.mask() - 1, square < self.width.mask() as u64)
+        };
+        if root {
+            heuristic_encode(self, Acc::from_raw(ceil));
+            self.push(Inst::S);
+        } else {
+            heuristic_encode(self, Acc::from_raw(max));
+        }
+        self.sub(self.acc.value() - n.value());
+        self.insts.push(Inst::O);
+    }
+
+    /// Encodes `n` like [`Builder::push_number`], unless it would exceed the
+    /// limit.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`LimitExceeded`] and leaves the builder unchanged, if the
+    /// encoding would exceed the limit.
+    #[inline]
+    pub fn try_push_number(&mut self, n: Acc) -> Result<(), LimitExceeded> {
+        self.check_limit(heuristic_len_with_config(self.acc, n, self.width, self.config) + 1)?;
+        self.push_number(n);
+        Ok(())
+    }
+
+    /// Encodes each number in sequence. Each transition continues from the
+    /// previous number, so nearby numbers are reached by a direct offset,
+    /// when it's shorter than routing through 0.
     #[inline]
     pub fn push_numbers<I: Iterator<Item = Acc>>(&mut self, numbers: I) {
-        for n in numbers {
+        self.push_numbers_with_progress(numbers, |_| {});
+    }
+
+    /// Encodes each number in sequence, like [`Builder::push_numbers`], and
+    /// calls `progress` with the index of each number after it's encoded.
+    #[inline]
+    pub fn push_numbers_with_progress<I, F>(&mut self, numbers: I, mut progress: F)
+    where
+        I: Iterator<Item = Acc>,
+        F: FnMut(usize),
+    {
+        for (i, n) in numbers.enumerate() {
             self.push_number(n);
+            progress(i);
         }
     }
 
     #[inline]
     pub fn push_string(&mut self, s: &str) {
-        for n in s.chars() {
-            // Encode Ā (256) as its decomposition, since it cannot be
-            // represented in Deadfish as-is.
-            if n == 'Ā' {
-                self.push_number(Acc::from_raw('A' as u32));
-                self.push_number(Acc::from_raw('\u{0304}' as u32));
+        for c in s.chars() {
+            self.push_char(c);
+        }
+    }
+
+    #[inline]
+    pub fn push_char(&mut self, c: char) {
+        for n in char_numbers(c) {
+            self.push_number(n);
+        }
+    }
+
+    /// Encodes `n` with the fewest squares, for interpreters where squaring
+    /// is costly, at the expense of longer offsets. It's reached by offsets
+    /// alone, when not crossing 256, and otherwise with at most one square.
+    pub fn push_number_min_squares(&mut self, n: Acc) {
+        let acc = self.acc;
+        if let Some(offset) = acc.offset_to(n) {
+            self.offset(offset);
+        } else if n < 256 {
+            // Reset to 0 by offsetting to 256 or -1
+            let down = acc.value() - 256;
+            let up = self.width.mask() - acc.value();
+            if down <= up {
+                self.sub(down);
             } else {
-                self.push_number(Acc::from_raw(n as u32));
+                self.add(up);
+            }
+            self.add(n.value());
+        } else {
+            // `acc` is below 256 and `n` above, so square a root below 256,
+            // then offset
+            let root = ((n.value() as f64).sqrt() as u32).clamp(17, 255);
+            self.offset(Offset(root as i64 - acc.value() as i64));
+            self.push(Inst::S);
+            self.offset(Offset(n.value() as i64 - (root * root) as i64));
+        }
+        debug_assert_eq!(n, self.acc);
+        self.insts.push(Inst::O);
+    }
+
+    /// Encodes each character, like [`Builder::push_string`], with the fewest
+    /// squares.
+    pub fn push_string_min_squares(&mut self, s: &str) {
+        for c in s.chars() {
+            for n in char_numbers(c) {
+                self.push_number_min_squares(n);
             }
         }
     }
@@ -89,16 +313,24 @@ impl Builder {
         }
     }
 
+    /// Appends the blank that the reference interpreter prints at the end of
+    /// input. It reads a program as a line, so the terminating line feed is an
+    /// unrecognized command and prints a final `">> \n"`.
+    #[inline]
+    pub fn finish_with_eof_prompt(&mut self) {
+        self.insts.push(Inst::Blank);
+    }
+
     #[inline]
     pub fn append(&mut self, insts: &[Inst]) {
         self.insts.extend_from_slice(insts);
-        self.acc = Inst::eval(insts, self.acc);
+        self.acc = self.width.eval(insts, self.acc);
     }
 
     #[inline]
     pub fn push(&mut self, inst: Inst) {
         self.insts.push(inst);
-        self.acc = self.acc.apply(inst);
+        self.acc = self.width.apply(self.acc, inst);
     }
 
     #[inline]
@@ -112,17 +344,75 @@ impl Builder {
 
     pub fn add(&mut self, x: u32) {
         self.push_repeat(Inst::I, x);
-        self.acc += x;
+        self.acc = self.width.add(self.acc, x);
+    }
+
+    /// Adds `delta` to the accumulator, like [`Builder::add`], but by the
+    /// heuristic encoding, so large deltas are reached by squaring instead of
+    /// a long run of increments. It's never longer than [`Builder::add`].
+    #[inline]
+    pub fn reach(&mut self, delta: u32) {
+        self.goto(self.width.add(self.acc, delta));
     }
 
     pub fn sub(&mut self, x: u32) {
         self.push_repeat(Inst::D, x);
-        self.acc -= x;
+        self.acc = self.width.sub(self.acc, x);
     }
 
     pub fn square(&mut self, count: u32) {
         self.push_repeat(Inst::S, count);
-        self.acc = self.acc.square_repeat(count);
+        self.acc = self.width.square_repeat(self.acc, count);
+    }
+
+    /// Adds `x` like [`Builder::add`], unless it would exceed the limit.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`LimitExceeded`] and leaves the builder unchanged, if the `x`
+    /// increments would exceed the limit.
+    #[inline]
+    pub fn try_add(&mut self, x: u32) -> Result<(), LimitExceeded> {
+        self.check_limit(x as usize)?;
+        self.add(x);
+        Ok(())
+    }
+
+    /// Subtracts `x` like [`Builder::sub`], unless it would exceed the limit.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`LimitExceeded`] and leaves the builder unchanged, if the `x`
+    /// decrements would exceed the limit.
+    #[inline]
+    pub fn try_sub(&mut self, x: u32) -> Result<(), LimitExceeded> {
+        self.check_limit(x as usize)?;
+        self.sub(x);
+        Ok(())
+    }
+
+    /// Squares `count` times like [`Builder::square`], unless it would exceed
+    /// the limit.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`LimitExceeded`] and leaves the builder unchanged, if the
+    /// `count` squares would exceed the limit.
+    #[inline]
+    pub fn try_square(&mut self, count: u32) -> Result<(), LimitExceeded> {
+        self.check_limit(count as usize)?;
+        self.square(count);
+        Ok(())
+    }
+
+    #[inline]
+    fn check_limit(&self, additional: usize) -> Result<(), LimitExceeded> {
+        let len = self.insts.len().saturating_add(additional);
+        if len > self.limit {
+            Err(LimitExceeded { len, limit: self.limit })
+        } else {
+            Ok(())
+        }
     }
 
     #[inline]
@@ -141,12 +431,50 @@ impl Builder {
     }
 }
 
+/// Converts a character to numbers. Ā (256) is converted to its
+/// decomposition, since it cannot be represented in Deadfish as-is.
+#[inline]
+fn char_numbers(c: char) -> impl Iterator<Item = Acc> {
+    let (c, combining) = if c == 'Ā' {
+        ('A', Some('\u{0304}'))
+    } else {
+        (c, None)
+    };
+    iter::once(c)
+        .chain(combining)
+        .map(|c| Acc::from_raw(c as u32))
+}
+
 impl From<Builder> for Vec<Inst> {
     fn from(b: Builder) -> Self {
         b.insts
     }
 }
 
+impl Display for LimitExceeded {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} instructions exceeds the limit of {}",
+            self.len, self.limit,
+        )
+    }
+}
+
+impl Error for LimitExceeded {}
+
+impl Display for PivotUnreachable {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} is unreachable by an offset from the squared pivot {}",
+            self.n, self.square,
+        )
+    }
+}
+
+impl Error for PivotUnreachable {}
+
 impl Default for Builder {
     fn default() -> Self {
         Self::new(Acc::new())
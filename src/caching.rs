@@ -0,0 +1,61 @@
+// Copyright (C) 2022 Andrew Archibald
+//
+// deadfish is free software: you can redistribute it and/or modify it under the
+// terms of the GNU Lesser General Public License as published by the Free
+// Software Foundation, either version 3 of the License, or (at your option) any
+// later version. You should have received a copy of the GNU Lesser General
+// Public License along with deadfish. If not, see http://www.gnu.org/licenses/.
+
+use std::collections::HashMap;
+
+use fxhash::FxBuildHasher;
+
+use crate::{Acc, Inst};
+
+/// Encodes transitions between numbers with the heuristic, remembering each
+/// `(acc, n)` transition, so it's encoded only once across many programs.
+#[derive(Clone, Debug, Default)]
+pub struct CachingEncoder {
+    cache: HashMap<(Acc, Acc), Vec<Inst>, FxBuildHasher>,
+}
+
+impl CachingEncoder {
+    #[must_use]
+    #[inline]
+    pub fn new() -> Self {
+        CachingEncoder::default()
+    }
+
+    /// Encodes `n` from `acc`, ending with `o`, like [`Inst::encode_number`].
+    #[must_use]
+    pub fn encode_number(&mut self, acc: Acc, n: Acc) -> &[Inst] {
+        self.cache
+            .entry((acc, n))
+            .or_insert_with(|| Inst::encode_number(acc, n))
+    }
+
+    /// Encodes the numbers from 0, like [`Inst::encode_numbers`].
+    #[must_use]
+    pub fn encode_numbers(&mut self, numbers: &[Acc]) -> Vec<Inst> {
+        let mut insts = Vec::new();
+        let mut acc = Acc::new();
+        for &n in numbers {
+            insts.extend_from_slice(self.encode_number(acc, n));
+            acc = n;
+        }
+        insts
+    }
+
+    /// Returns the number of distinct transitions encoded.
+    #[must_use]
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.cache.len()
+    }
+
+    #[must_use]
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.cache.is_empty()
+    }
+}
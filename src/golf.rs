@@ -0,0 +1,275 @@
+// Copyright (C) 2022 Andrew Archibald
+//
+// deadfish is free software: you can redistribute it and/or modify it under the
+// terms of the GNU Lesser General Public License as published by the Free
+// Software Foundation, either version 3 of the License, or (at your option) any
+// later version. You should have received a copy of the GNU Lesser General
+// Public License along with deadfish. If not, see http://www.gnu.org/licenses/.
+
+//! Shortest known programs for `0 -> n`, curated from Code Golf and the
+//! Esolang wiki. They are consistent with an exhaustive search with
+//! `BfsEncoder` for `0 -> 1..=248`, which state-explodes beyond that.
+//!
+//! - <https://codegolf.stackexchange.com/questions/40124/short-deadfish-numbers>
+//! - <https://esolangs.org/wiki/Deadfish/Constants>
+
+/// Programs, which output `n` from 0, sorted by `n`.
+pub(crate) static GOLF_CONSTANTS: [(u32, &str); 257] = [
+    (0, "o"),
+    (1, "io"),
+    (2, "iio"),
+    (3, "iiio"),
+    (4, "iiso"),
+    (5, "iisio"),
+    (6, "iisiio"),
+    (7, "iiisddo"),
+    (8, "iiisdo"),
+    (9, "iiiso"),
+    (10, "iiisio"),
+    (11, "iiisiio"),
+    (12, "iiisiiio"),
+    (13, "iissdddo"),
+    (14, "iissddo"),
+    (15, "iissdo"),
+    (16, "iisso"),
+    (17, "iissio"),
+    (18, "iissiio"),
+    (19, "iissiiio"),
+    (20, "iissiiiio"),
+    (21, "iisisddddo"),
+    (22, "iisisdddo"),
+    (23, "iisisddo"),
+    (24, "iisisdo"),
+    (25, "iisiso"),
+    (26, "iisisio"),
+    (27, "iisisiio"),
+    (28, "iisisiiio"),
+    (29, "iisisiiiio"),
+    (30, "iisisiiiiio"),
+    (31, "iisiisdddddo"),
+    (32, "iisiisddddo"),
+    (33, "iisiisdddo"),
+    (34, "iisiisddo"),
+    (35, "iisiisdo"),
+    (36, "iisiiso"),
+    (37, "iisiisio"),
+    (38, "iisiisiio"),
+    (39, "iisiisiiio"),
+    (40, "iisiisiiiio"),
+    (41, "iisiisiiiiio"),
+    (42, "iisiisiiiiiio"),
+    (43, "iiisddsddddddo"),
+    (44, "iiisddsdddddo"),
+    (45, "iiisddsddddo"),
+    (46, "iiisddsdddo"),
+    (47, "iiisddsddo"),
+    (48, "iiisddsdo"),
+    (49, "iiisddso"),
+    (50, "iiisddsio"),
+    (51, "iiisddsiio"),
+    (52, "iiisddsiiio"),
+    (53, "iiisddsiiiio"),
+    (54, "iiisddsiiiiio"),
+    (55, "iiisddsiiiiiio"),
+    (56, "iiisddsiiiiiiio"),
+    (57, "iiisdsdddddddo"),
+    (58, "iiisdsddddddo"),
+    (59, "iiisdsdddddo"),
+    (60, "iiisdsddddo"),
+    (61, "iiisdsdddo"),
+    (62, "iiisdsddo"),
+    (63, "iiisdsdo"),
+    (64, "iiisdso"),
+    (65, "iiisdsio"),
+    (66, "iiisdsiio"),
+    (67, "iiisdsiiio"),
+    (68, "iiisdsiiiio"),
+    (69, "iiisdsiiiiio"),
+    (70, "iiisdsiiiiiio"),
+    (71, "iiisdsiiiiiiio"),
+    (72, "iiisdsiiiiiiiio"),
+    (73, "iiissddddddddo"),
+    (74, "iiissdddddddo"),
+    (75, "iiissddddddo"),
+    (76, "iiissdddddo"),
+    (77, "iiissddddo"),
+    (78, "iiissdddo"),
+    (79, "iiissddo"),
+    (80, "iiissdo"),
+    (81, "iiisso"),
+    (82, "iiissio"),
+    (83, "iiissiio"),
+    (84, "iiissiiio"),
+    (85, "iiissiiiio"),
+    (86, "iiissiiiiio"),
+    (87, "iiissiiiiiio"),
+    (88, "iiissiiiiiiio"),
+    (89, "iiissiiiiiiiio"),
+    (90, "iiissiiiiiiiiio"),
+    (91, "iiisisdddddddddo"),
+    (92, "iiisisddddddddo"),
+    (93, "iiisisdddddddo"),
+    (94, "iiisisddddddo"),
+    (95, "iiisisdddddo"),
+    (96, "iiisisddddo"),
+    (97, "iiisisdddo"),
+    (98, "iiisisddo"),
+    (99, "iiisisdo"),
+    (100, "iiisiso"),
+    (101, "iiisisio"),
+    (102, "iiisisiio"),
+    (103, "iiisisiiio"),
+    (104, "iiisisiiiio"),
+    (105, "iiisisiiiiio"),
+    (106, "iiisisiiiiiio"),
+    (107, "iiisisiiiiiiio"),
+    (108, "iiisisiiiiiiiio"),
+    (109, "iiisisiiiiiiiiio"),
+    (110, "iiisisiiiiiiiiiio"),
+    (111, "iiisiisddddddddddo"),
+    (112, "iiisiisdddddddddo"),
+    (113, "iiisiisddddddddo"),
+    (114, "iiisiisdddddddo"),
+    (115, "iiisiisddddddo"),
+    (116, "iiisiisdddddo"),
+    (117, "iiisiisddddo"),
+    (118, "iiisiisdddo"),
+    (119, "iiisiisddo"),
+    (120, "iiisiisdo"),
+    (121, "iiisiiso"),
+    (122, "iiisiisio"),
+    (123, "iiisiisiio"),
+    (124, "iiisiisiiio"),
+    (125, "iiisiisiiiio"),
+    (126, "iiisiisiiiiio"),
+    (127, "iiisiisiiiiiio"),
+    (128, "iiisiisiiiiiiio"),
+    (129, "iiisiisiiiiiiiio"),
+    (130, "iiisiisiiiiiiiiio"),
+    (131, "iiisiisiiiiiiiiiio"),
+    (132, "iiisiisiiiiiiiiiiio"),
+    (133, "iiisiiisdddddddddddo"),
+    (134, "iiisiiisddddddddddo"),
+    (135, "iiisiiisdddddddddo"),
+    (136, "iiisiiisddddddddo"),
+    (137, "iiisiiisdddddddo"),
+    (138, "iiisiiisddddddo"),
+    (139, "iiisiiisdddddo"),
+    (140, "iiisiiisddddo"),
+    (141, "iiisiiisdddo"),
+    (142, "iiisiiisddo"),
+    (143, "iiisiiisdo"),
+    (144, "iiisiiiso"),
+    (145, "iiisiiisio"),
+    (146, "iiisiiisiio"),
+    (147, "iiisiiisiiio"),
+    (148, "iiisiiisiiiio"),
+    (149, "iiisiiisiiiiio"),
+    (150, "iiisiiisiiiiiio"),
+    (151, "iiisiiisiiiiiiio"),
+    (152, "iiisiiisiiiiiiiio"),
+    (153, "iiisiiisiiiiiiiiio"),
+    (154, "iiisiiisiiiiiiiiiio"),
+    (155, "iiisiiisiiiiiiiiiiio"),
+    (156, "iiisiiisiiiiiiiiiiiio"),
+    (157, "iissdddsddddddddddddo"),
+    (158, "iissdddsdddddddddddo"),
+    (159, "iissdddsddddddddddo"),
+    (160, "iissdddsdddddddddo"),
+    (161, "iissdddsddddddddo"),
+    (162, "iissdddsdddddddo"),
+    (163, "iissdddsddddddo"),
+    (164, "iissdddsdddddo"),
+    (165, "iissdddsddddo"),
+    (166, "iissdddsdddo"),
+    (167, "iissdddsddo"),
+    (168, "iissdddsdo"),
+    (169, "iissdddso"),
+    (170, "iissdddsio"),
+    (171, "iissdddsiio"),
+    (172, "iissdddsiiio"),
+    (173, "iissdddsiiiio"),
+    (174, "iissdddsiiiiio"),
+    (175, "iissdddsiiiiiio"),
+    (176, "iissdddsiiiiiiio"),
+    (177, "iissdddsiiiiiiiio"),
+    (178, "iissdddsiiiiiiiiio"),
+    (179, "iissdddsiiiiiiiiiio"),
+    (180, "iissdddsiiiiiiiiiiio"),
+    (181, "iissdddsiiiiiiiiiiiio"),
+    (182, "iissdddsiiiiiiiiiiiiio"),
+    (183, "iissddsdddddddddddddo"),
+    (184, "iissddsddddddddddddo"),
+    (185, "iissddsdddddddddddo"),
+    (186, "iissddsddddddddddo"),
+    (187, "iissddsdddddddddo"),
+    (188, "iissddsddddddddo"),
+    (189, "iissddsdddddddo"),
+    (190, "iissddsddddddo"),
+    (191, "iissddsdddddo"),
+    (192, "iissddsddddo"),
+    (193, "iissddsdddo"),
+    (194, "iissddsddo"),
+    (195, "iissddsdo"),
+    (196, "iissddso"),
+    (197, "iissddsio"),
+    (198, "iissddsiio"),
+    (199, "iissddsiiio"),
+    (200, "iissddsiiiio"),
+    (201, "iissddsiiiiio"),
+    (202, "iissddsiiiiiio"),
+    (203, "iissddsiiiiiiio"),
+    (204, "iissddsiiiiiiiio"),
+    (205, "iissddsiiiiiiiiio"),
+    (206, "iissddsiiiiiiiiiio"),
+    (207, "iissddsiiiiiiiiiiio"),
+    (208, "iissddsiiiiiiiiiiiio"),
+    (209, "iissddsiiiiiiiiiiiiio"),
+    (210, "iissddsiiiiiiiiiiiiiio"),
+    (211, "iissdsddddddddddddddo"),
+    (212, "iissdsdddddddddddddo"),
+    (213, "iissdsddddddddddddo"),
+    (214, "iissdsdddddddddddo"),
+    (215, "iissdsddddddddddo"),
+    (216, "iissdsdddddddddo"),
+    (217, "iissdsddddddddo"),
+    (218, "iissdsdddddddo"),
+    (219, "iissdsddddddo"),
+    (220, "iissdsdddddo"),
+    (221, "iissdsddddo"),
+    (222, "iissdsdddo"),
+    (223, "iissdsddo"),
+    (224, "iissdsdo"),
+    (225, "iissdso"),
+    (226, "iissdsio"),
+    (227, "iissdsiio"),
+    (228, "iissdsiiio"),
+    (229, "iissdsiiiio"),
+    (230, "iissdsiiiiio"),
+    (231, "iissdsiiiiiio"),
+    (232, "iissdsiiiiiiio"),
+    (233, "iissdsiiiiiiiio"),
+    (234, "iissdsiiiiiiiiio"),
+    (235, "iissdsiiiiiiiiiio"),
+    (236, "iissdsiiiiiiiiiiio"),
+    (237, "iissdsiiiiiiiiiiiio"),
+    (238, "iissdsiiiiiiiiiiiiio"),
+    (239, "iissdsiiiiiiiiiiiiiio"),
+    (240, "iissdsiiiiiiiiiiiiiiio"),
+    (241, "iissdsiiiiiiiiiiiiiiiio"),
+    (242, "iissdsiiiiiiiiiiiiiiiiio"),
+    (243, "iissdsiiiiiiiiiiiiiiiiiio"),
+    (244, "iissdsiiiiiiiiiiiiiiiiiiio"),
+    (245, "iissdsiiiiiiiiiiiiiiiiiiiio"),
+    (246, "iissdsiiiiiiiiiiiiiiiiiiiiio"),
+    (247, "iissdsiiiiiiiiiiiiiiiiiiiiiio"),
+    (248, "iissdsiiiiiiiiiiiiiiiiiiiiiiio"),
+    (249, "iissdsiiiiiiiiiiiiiiiiiiiiiiiio"),
+    (250, "iissdsiiiiiiiiiiiiiiiiiiiiiiiiio"),
+    (251, "iissdsiiiiiiiiiiiiiiiiiiiiiiiiiio"),
+    (252, "iissdsiiiiiiiiiiiiiiiiiiiiiiiiiiio"),
+    (253, "iissdsiiiiiiiiiiiiiiiiiiiiiiiiiiiio"),
+    (254, "iissdsiiiiiiiiiiiiiiiiiiiiiiiiiiiiio"),
+    (255, "iissdsiiiiiiiiiiiiiiiiiiiiiiiiiiiiiio"),
+    (257, "iissisddddddddddddddddddddddddddddddddo"),
+];
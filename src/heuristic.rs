@@ -18,35 +18,144 @@
 
 use std::collections::VecDeque;
 
-use crate::{Acc, Builder, Offset};
+use crate::{Acc, Builder, Inst, Offset, Width};
+
+/// Tunes the choice of route by the heuristic encoder, e.g., to measure the
+/// effect on total encoding length over a range of values.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct HeuristicConfig {
+    /// Number of instructions a direct offset may exceed the shortest route
+    /// with squares by and still be chosen. A positive bias prefers offsets
+    /// and a negative bias prefers squares. The default of 0 chooses an
+    /// offset, when it's no longer.
+    pub prefer_offset_bias: i32,
+}
 
 pub(crate) fn heuristic_encode(b: &mut Builder, n: Acc) {
     let acc = b.acc();
+    let start = b.insts().len();
+    let (plan, len) = plan(acc, n, b.width(), b.heuristic_config());
+    match plan {
+        Plan::Offset(offset) => b.offset(offset),
+        Plan::ViaRoot(offsets) => b.offset_squares(&offsets),
+        Plan::Via0(offset_to_0, squares_to_0, offsets_from_0) => {
+            b.offset(offset_to_0);
+            b.square(squares_to_0);
+            b.offset_squares(&offsets_from_0);
+        }
+    }
+    debug_assert_eq!(n, b.acc(), "acc={acc} {:?}", &b.insts()[start..]);
+    debug_assert_eq!(len, b.insts().len() - start);
+}
+
+/// Encodes `n` without comparing routes: it offsets directly, when possible,
+/// and otherwise resets to 0 and follows the square chain of `n`. It takes
+/// time linear in the emitted instructions, but can be much longer than
+/// `heuristic_encode`, since a direct offset is taken no matter its length and
+/// the chain is never entered at a root near `acc`.
+pub(crate) fn fast_encode(b: &mut Builder, n: Acc) {
+    if let Some(offset) = b.acc().offset_to(n) {
+        b.offset(offset);
+    } else {
+        let (offset_to_0, squares_to_0) = encode_to_0(b.acc(), b.width());
+        b.offset(offset_to_0);
+        b.square(squares_to_0);
+        b.offset_squares(&encode_from_0(n, b.width()).0);
+    }
+    debug_assert_eq!(n, b.acc());
+}
+
+/// Increases the accumulator from 0 to `n` with only `i` and `s`, by squaring
+/// a root no greater than `n` and incrementing the rest.
+pub(crate) fn increase_to(b: &mut Builder, n: u32) {
+    if n < 256 {
+        b.add(n);
+        return;
+    }
+    let (root, square) = match (n as f64).sqrt() as u32 {
+        // 17^2 is 289, so smaller values are squared to from a wrapping root,
+        // which exists for 257, so for some value no greater than `n`
+        0..=16 => (257..=n)
+            .rev()
+            .find_map(|t| {
+                let roots = Acc::from(t).wrapping_sqrts_in_domain();
+                let root = roots
+                    .into_iter()
+                    .find(|&r| !(256..=288).contains(&r.value()))?;
+                Some((root.value(), t))
+            })
+            .unwrap(),
+        // 256 can't be a root, and 257 through 288 have roots of at most 16,
+        // so increasing to them would recurse into the wrapping root search.
+        // Square 255 instead, which is no greater.
+        256..=288 => (255, 255 * 255),
+        root => (root, root * root),
+    };
+    increase_to(b, root);
+    b.push(Inst::S);
+    b.add(n - square);
+}
+
+/// Computes the number of instructions `heuristic_encode` uses to encode `n`
+/// from `acc`, without constructing them.
+#[must_use]
+pub(crate) fn heuristic_len(acc: Acc, n: Acc, width: Width) -> usize {
+    heuristic_len_with_config(acc, n, width, HeuristicConfig::default())
+}
 
+/// Computes the number of instructions `heuristic_encode` uses to encode `n`
+/// from `acc` with the given config.
+#[must_use]
+pub(crate) fn heuristic_len_with_config(
+    acc: Acc,
+    n: Acc,
+    width: Width,
+    config: HeuristicConfig,
+) -> usize {
+    plan(acc, n, width, config).1
+}
+
+/// A route chosen by the heuristic.
+enum Plan {
+    /// Offset directly.
+    Offset(Offset),
+    /// Offset to a root in the square chain of `n`, then square and offset.
+    ViaRoot(VecDeque<Offset>),
+    /// Offset and square to 0, then offset and square from 0.
+    Via0(Offset, u32, VecDeque<Offset>),
+}
+
+fn plan(acc: Acc, n: Acc, width: Width, config: HeuristicConfig) -> (Plan, usize) {
     let simple_offset = acc.offset_to(n);
 
-    let (offset_to_0, squares_to_0) = encode_to_0(acc);
-    let (offsets_from_0, len_from_0) = encode_from_0(n);
+    // Offsetting to -1 resets to 0 just like 256, so both resets are covered by
+    // the path via 0.
+    let (offset_to_0, squares_to_0) = encode_to_0(acc, width);
+    let (offsets_from_0, len_from_0) = encode_from_0(n, width);
     let len_via_0 = offset_to_0.len() + squares_to_0 as usize + len_from_0;
 
-    let start = b.insts().len();
-    if simple_offset.is_some_and(|&offset| offset.len() <= len_via_0) {
-        b.offset(simple_offset.unwrap());
+    let via_root = encode_via_root(acc, &offsets_from_0, width);
+    let len_via_root = via_root.as_ref().map_or(usize::MAX, |(_, len)| *len);
+
+    let len_squares = len_via_0.min(len_via_root) as i64 + config.prefer_offset_bias as i64;
+    if simple_offset.is_some_and(|&offset| offset.len() as i64 <= len_squares) {
+        let offset = simple_offset.unwrap();
+        (Plan::Offset(offset), offset.len())
+    } else if len_via_root < len_via_0 {
+        (Plan::ViaRoot(via_root.unwrap().0), len_via_root)
     } else {
-        b.offset(offset_to_0);
-        b.square(squares_to_0);
-        b.offset_squares(&offsets_from_0);
+        let plan = Plan::Via0(offset_to_0, squares_to_0, offsets_from_0);
+        (plan, len_via_0)
     }
-    debug_assert_eq!(n, b.acc(), "acc={acc} {:?}", &b.insts()[start..]);
 }
 
 #[must_use]
-pub(crate) fn encode_from_0(n: Acc) -> (VecDeque<Offset>, usize) {
+pub(crate) fn encode_from_0(n: Acc, width: Width) -> (VecDeque<Offset>, usize) {
     let mut n = n;
     let mut offsets = VecDeque::new();
     let mut len = 0;
     while n >= 4 {
-        let (sqrt, offset) = n.nearest_sqrt();
+        let (sqrt, offset) = width.nearest_sqrt(n);
         offsets.push_front(offset);
         len += offset.len() + 1;
         n = sqrt;
@@ -56,12 +165,50 @@ pub(crate) fn encode_from_0(n: Acc) -> (VecDeque<Offset>, usize) {
     (offsets, len)
 }
 
+/// Finds the shortest path from `acc` to `n`, that offsets directly to one of
+/// the roots in the square chain of `n` (as constructed by `encode_from_0`),
+/// without first resetting to 0. This allows crossing 256 by squaring, when
+/// `acc` is near a root.
+#[must_use]
+fn encode_via_root(
+    acc: Acc,
+    offsets_from_0: &VecDeque<Offset>,
+    width: Width,
+) -> Option<(VecDeque<Offset>, usize)> {
+    let mut best: Option<(usize, Offset, usize)> = None;
+    let mut root = Acc::new();
+    let mut len_from_root: usize = offsets_from_0
+        .iter()
+        .skip(1)
+        .map(|offset| offset.len() + 1)
+        .sum();
+    for (i, &offset) in offsets_from_0.iter().enumerate() {
+        if i == 0 {
+            root = width.offset(root, offset);
+        } else {
+            root = width.offset(width.apply(root, Inst::S), offset);
+            len_from_root -= offset.len() + 1;
+        }
+        if let Some(offset_to_root) = acc.offset_to(root) {
+            let len = offset_to_root.len() + len_from_root;
+            if !matches!(best, Some((_, _, best_len)) if best_len <= len) {
+                best = Some((i, offset_to_root, len));
+            }
+        }
+    }
+    best.map(|(i, offset_to_root, len)| {
+        let mut offsets: VecDeque<_> = offsets_from_0.iter().skip(i + 1).copied().collect();
+        offsets.push_front(offset_to_root);
+        (offsets, len)
+    })
+}
+
 /// Finds the shortest path from `acc` to 0, preferring fewer squares as a tie
 /// breaker.
 #[inline]
-const fn encode_to_0(n: Acc) -> (Offset, u32) {
-    let (offset1, squares1) = encode_to_zero_no_overflow(n);
-    let (offset2, squares2) = encode_to_zero_overflow(n);
+const fn encode_to_0(n: Acc, width: Width) -> (Offset, u32) {
+    let (offset1, squares1) = encode_to_zero_no_overflow(n, width);
+    let (offset2, squares2) = encode_to_zero_overflow(n, width);
     let len1 = offset1.abs() + squares1;
     let len2 = offset2.abs() + squares2;
     if len1 < len2 || len1 == len2 && squares1 <= squares2 {
@@ -72,30 +219,30 @@ const fn encode_to_0(n: Acc) -> (Offset, u32) {
 }
 
 #[inline]
-const fn encode_to_zero_no_overflow(n: Acc) -> (Offset, u32) {
+const fn encode_to_zero_no_overflow(n: Acc, width: Width) -> (Offset, u32) {
     const LOW_16: u32 = (4 + 16) / 2;
     const LOW_256: u32 = (16 + 256) / 2;
-    const LOW_NEG: u32 = u32::MAX / 2 + 256 / 2;
+    let low_neg = width.mask() / 2 + 256 / 2;
     let (target, squares) = match n.value() {
         // Offset to 0
         0..4 => (0, 0),
         // Offset and square to 256
         4..LOW_16 => (4, 2),
         LOW_16..LOW_256 => (16, 1),
-        LOW_256..LOW_NEG => (256, 0),
+        n if n < low_neg => (256, 0),
         // Offset to -1
-        LOW_NEG.. => (u32::MAX, 0),
-        // Cases for squaring to `x << 32` are not necessary here, because each
-        // of those roots have at least 16 trailing zeros and are covered by
-        // `encode_to_zero_overflow`.
+        _ => (width.mask(), 0),
+        // Cases for squaring to `x << bits` are not necessary here, because
+        // each of those roots have at least `bits / 2` trailing zeros and are
+        // covered by `encode_to_zero_overflow`.
     };
     (Offset(target as i64 - n.value() as i64), squares)
 }
 
 #[inline]
-const fn encode_to_zero_overflow(n: Acc) -> (Offset, u32) {
+const fn encode_to_zero_overflow(n: Acc, width: Width) -> (Offset, u32) {
     let mut n = n.value();
-    let mut tz = n.trailing_zeros();
+    let tz = n.trailing_zeros();
     let mut offset = Offset(0);
     if tz < 2 {
         offset = if tz == 1 {
@@ -115,16 +262,16 @@ const fn encode_to_zero_overflow(n: Acc) -> (Offset, u32) {
                 _ => Offset((n & 0b11) as i64 - 2),
             }
         };
-        n = (Acc::from_raw(n) + offset).value();
-        tz = n.trailing_zeros();
+        n = width.offset(Acc::from_raw(n), offset).value();
+    }
+    // Square until there are `bits` trailing zeros; each square doubles the
+    // trailing zeros. The chain may reset to 0 sooner by passing through 256
+    // (e.g., for roots and fourth roots of 256), so count the squares that
+    // are actually applied, like `Width::square_repeat`.
+    let mut squares = 0;
+    while width.normalize(n) != 0 {
+        n = n.wrapping_mul(n);
+        squares += 1;
     }
-    let squares = if n.wrapping_mul(n) == 256 {
-        // Square once, if n is a modular square root of 256
-        1
-    } else {
-        // Square until there are 32 trailing zeros; equivalent to
-        // log2(32) - floor(log2(tz))
-        tz.leading_zeros() - 32u32.leading_zeros()
-    };
     (offset, squares)
 }
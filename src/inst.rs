@@ -6,9 +6,15 @@
 // later version. You should have received a copy of the GNU Lesser General
 // Public License along with deadfish. If not, see http://www.gnu.org/licenses/.
 
-use std::io::{self, Write};
+use std::collections::{HashMap, HashSet};
+use std::fmt::{self, Display, Formatter};
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::mem;
 
-use crate::{Acc, Builder};
+use crate::{
+    heuristic_len, increase_to, Acc, BfsEncoder, Builder, CachingEncoder, Offset, Width,
+    GOLF_CONSTANTS,
+};
 
 /// Deadfish instructions.
 #[repr(u8)]
@@ -27,12 +33,63 @@ pub enum Inst {
 }
 
 impl Inst {
+    /// Gets the character for the instruction, with a space for blanks.
+    #[must_use]
+    #[inline]
+    pub const fn as_char(self) -> char {
+        match self {
+            Inst::I => 'i',
+            Inst::D => 'd',
+            Inst::S => 's',
+            Inst::O => 'o',
+            Inst::Blank => ' ',
+        }
+    }
+
     #[must_use]
     #[inline]
     pub fn eval(insts: &[Inst], acc: Acc) -> Acc {
         insts.iter().fold(acc, |acc, &inst| acc.apply(inst))
     }
 
+    /// Evaluates the instructions from `acc` and checks whether any resets
+    /// the accumulator to 0 from 256 or -1, so the program depends on the
+    /// reset. This is unrelated to whether it outputs 0.
+    #[must_use]
+    pub fn uses_reset(insts: &[Inst], acc: Acc) -> bool {
+        let mut acc = acc;
+        for &inst in insts {
+            let (next, reset) = acc.apply_checked(inst);
+            if reset {
+                return true;
+            }
+            acc = next;
+        }
+        false
+    }
+
+    /// Estimates the time to run the program on an interpreter, that takes
+    /// `costs[inst as usize]` cycles for each instruction, i.e., in the order
+    /// `i`, `d`, `s`, `o`, and blank.
+    #[must_use]
+    #[inline]
+    pub fn estimated_cycles(insts: &[Inst], costs: [u64; 5]) -> u64 {
+        insts.iter().map(|&inst| costs[inst as usize]).sum()
+    }
+
+    /// Evaluates the instructions with plain wrapping `int` arithmetic, as the
+    /// reference implementation would without resetting 256 and -1 to 0. This
+    /// models buggy interpreters and differs from [`Inst::eval`].
+    #[must_use]
+    pub fn eval_unnormalized(insts: &[Inst], acc: i32) -> i32 {
+        insts.iter().fold(acc, |acc, &inst| match inst {
+            Inst::I => acc.wrapping_add(1),
+            Inst::D => acc.wrapping_sub(1),
+            Inst::S => acc.wrapping_mul(acc),
+            Inst::O | Inst::Blank => acc,
+        })
+    }
+
     #[must_use]
     #[inline]
     pub fn encode_number(acc: Acc, n: Acc) -> Vec<Inst> {
@@ -41,14 +98,299 @@ impl Inst {
         b.into()
     }
 
+    /// Checks that a path from any encoder ends in `o` and prints `n`, when
+    /// starting from `acc`.
+    #[must_use]
+    #[inline]
+    pub fn verify_path(acc: Acc, n: Acc, path: &[Inst]) -> bool {
+        match path.split_last() {
+            Some((Inst::O, path)) => Inst::eval(path, acc) == n,
+            _ => false,
+        }
+    }
+
+    /// Constructs the shortest known program from 0 for each printable ASCII
+    /// character, `' '` through `'~'`, by [`Inst::encode_number_golf`], for a
+    /// lookup-based text encoder.
+    #[must_use]
+    pub fn ascii_encoding_table() -> [Vec<Inst>; 95] {
+        let mut c = b' ';
+        [(); 95].map(|()| {
+            let program = Inst::encode_number_golf(Acc::from(c as u32));
+            c += 1;
+            program
+        })
+    }
+
+    /// Encodes `n` from `acc` like [`Inst::encode_number`], but without
+    /// comparing routes, so it never searches. See
+    /// [`Builder::push_number_fast`] for the optimality gap.
+    #[must_use]
+    #[inline]
+    pub fn encode_number_fast(acc: Acc, n: Acc) -> Vec<Inst> {
+        let mut b = Builder::new(acc);
+        b.push_number_fast(n);
+        b.into()
+    }
+
+    /// Encodes `n` printed `count` times. The accumulator doesn't change
+    /// between identical outputs, so it's reached once, then printed with a
+    /// run of `o`.
+    #[must_use]
+    pub fn encode_repeat(n: Acc, count: u32) -> Vec<Inst> {
+        if count == 0 {
+            return Vec::new();
+        }
+        let mut b = Builder::new(Acc::new());
+        b.goto(n);
+        let mut insts = b.into_insts();
+        insts.resize(insts.len() + count as usize, Inst::O);
+        insts
+    }
+
+    /// Encodes `n` from `acc`, so that the accumulator never reaches 256 or -1,
+    /// making the program portable to interpreters that don't reset. Such a
+    /// route always exists: when `acc` is above 256, it can wrap to 0 by
+    /// squaring a multiple of 256, and when `n` is above 256, it's reached from
+    /// 0 by squaring a root below 256. The heuristic encoding is used, when it
+    /// doesn't reset.
+    #[must_use]
+    pub fn encode_number_no_reset(acc: Acc, n: Acc) -> Vec<Inst> {
+        let heuristic = Inst::encode_number(acc, n);
+        if !Inst::uses_reset(&heuristic, acc) {
+            return heuristic;
+        }
+
+        // Wrap to 0, then encode from 0
+        let mut b = Builder::new(acc);
+        if acc < 256 {
+            b.sub(acc.value());
+        } else {
+            // Squaring a multiple of 256 twice, or of 65536 once, wraps to 0
+            let x = acc.value();
+            let nearest = |m: u32| {
+                let floor = (x / m * m).max(m.max(512));
+                let ceil = (x / m + 1).saturating_mul(m).min(0u32.wrapping_sub(m));
+                if floor.abs_diff(x) <= ceil.abs_diff(x) {
+                    floor
+                } else {
+                    ceil
+                }
+            };
+            let (root_256, root_65536) = (nearest(256), nearest(65536));
+            let (root, squares) = if root_65536.abs_diff(x) + 1 < root_256.abs_diff(x) + 2 {
+                (root_65536, 1)
+            } else {
+                (root_256, 2)
+            };
+            // Both `acc` and `root` are above 256
+            b.offset(Offset(root as i64 - x as i64));
+            b.square(squares);
+        }
+        let from_0 = Inst::encode_number(Acc::new(), n);
+        if Inst::uses_reset(&from_0, Acc::new()) {
+            b.push_number_min_squares(n);
+        } else {
+            b.append(&from_0);
+        }
+        let via_0 = b.into_insts();
+
+        match acc.offset_to(n) {
+            Some(offset) if offset.len() < via_0.len() => {
+                let mut b = Builder::new(acc);
+                b.offset(offset);
+                b.push(Inst::O);
+                b.into()
+            }
+            _ => via_0,
+        }
+    }
+
+    /// Encodes `n` from 0 using the fewest kinds of instructions, besides the
+    /// final `o`, as a puzzle variant. 0 needs none, values below 256 are
+    /// reached by `i` alone, and values above 256 need `i` and `s`, since `i`
+    /// alone resets at 256, and `d` and `s` alone can't leave 0. It's not the
+    /// shortest such program and can be long for large values.
+    #[must_use]
+    pub fn encode_min_instruction_kinds(n: Acc) -> Vec<Inst> {
+        let mut b = Builder::new(Acc::new());
+        increase_to(&mut b, n.value());
+        b.push(Inst::O);
+        b.into()
+    }
+
+    /// Encodes `n` from 0 with the shortest known program from Code Golf and
+    /// the Esolang wiki, which covers 0 through 257, except 256. These are the
+    /// shortest known, but only 1 through 248 have been verified with
+    /// `BfsEncoder`, which state-explodes beyond that. Other numbers fall back
+    /// to the heuristic.
+    #[must_use]
+    pub fn encode_number_golf(n: Acc) -> Vec<Inst> {
+        match GOLF_CONSTANTS.binary_search_by_key(&n.value(), |&(n, _)| n) {
+            Ok(i) => Inst::parse(GOLF_CONSTANTS[i].1),
+            Err(_) => Inst::encode_number(Acc::new(), n),
+        }
+    }
+
     #[must_use]
     #[inline]
     pub fn encode_numbers(ir: &Vec<Acc>) -> Vec<Inst> {
+        Self::encode_numbers_from(Acc::new(), ir)
+    }
+
+    /// Encodes the numbers from any integer type that converts to `i32`, so
+    /// callers need not convert to [`Acc`] first.
+    #[must_use]
+    pub fn encode<T: Into<i32>>(numbers: impl IntoIterator<Item = T>) -> Vec<Inst> {
+        let mut b = Builder::new(Acc::new());
+        b.push_numbers(numbers.into_iter().map(|n| Acc::from(n.into())));
+        b.into()
+    }
+
+    /// Encodes the numbers, continuing from an accumulator of `acc`.
+    #[must_use]
+    #[inline]
+    pub fn encode_numbers_from(acc: Acc, numbers: &[Acc]) -> Vec<Inst> {
+        let mut b = Builder::new(acc);
+        b.push_numbers(numbers.iter().copied());
+        b.into()
+    }
+
+    /// Encodes the character `c` from `acc`. Ā (256) is encoded as its
+    /// decomposition, so outputs two numbers.
+    #[must_use]
+    #[inline]
+    pub fn encode_char(acc: Acc, c: char) -> Vec<Inst> {
+        let mut b = Builder::new(acc);
+        b.push_char(c);
+        b.into()
+    }
+
+    /// Encodes the characters of `s`, continuing from an accumulator of `acc`,
+    /// such as after printing a previous string.
+    #[must_use]
+    #[inline]
+    pub fn encode_str_continuation(acc: Acc, s: &str) -> Vec<Inst> {
+        let mut b = Builder::new(acc);
+        b.push_string(s);
+        b.into()
+    }
+
+    /// Encodes a number for each position, choosing from the allowed values
+    /// the one with the fewest decimal digits when printed. Ties are broken by
+    /// the shortest encoding from the previous number.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a position has no allowed values.
+    #[must_use]
+    pub fn encode_min_output_width(allowed: &[&[Acc]]) -> Vec<Inst> {
+        let mut b = Builder::new(Acc::new());
+        for &values in allowed {
+            let acc = b.acc();
+            let n = values
+                .iter()
+                .copied()
+                .min_by_key(|&n| (n.decimal_len(), Inst::encode_number(acc, n).len()))
+                .expect("no allowed values");
+            b.push_number(n);
+        }
+        b.into()
+    }
+
+    /// Encodes the decimal digits of `n` as separate numbers, reaching each
+    /// digit by an offset from the previous.
+    #[must_use]
+    pub fn encode_decimal_digits(n: u32) -> Vec<Inst> {
+        let digits = n.to_string();
+        let mut b = Builder::new(Acc::new());
+        b.push_numbers(digits.bytes().map(|d| Acc::from((d - b'0') as u32)));
+        b.into()
+    }
+
+    /// Encodes each number as it arrives and writes the instructions as text,
+    /// keeping only the current accumulator, so the stream can be unbounded.
+    ///
+    /// # Errors
+    ///
+    /// Returns any error from writing to `out`.
+    pub fn encode_stream<I, W>(numbers: I, out: &mut W) -> io::Result<()>
+    where
+        I: Iterator<Item = i32>,
+        W: Write,
+    {
+        let mut b = Builder::new(Acc::new());
+        let mut text = Vec::new();
+        for n in numbers {
+            b.push_number(Acc::from(n));
+            text.clear();
+            text.extend(b.insts().iter().map(|inst| inst.as_char() as u8));
+            out.write_all(&text)?;
+            b.reset(b.acc());
+        }
+        out.flush()
+    }
+
+    /// Appends an encoding of `numbers` to the instructions, continuing from
+    /// their final accumulator, instead of from 0.
+    #[must_use]
+    pub fn concat_optimal(insts: &[Inst], numbers: &[Acc]) -> Vec<Inst> {
+        let mut b = Builder::from_insts(insts.to_vec(), Inst::eval(insts, Acc::new()));
+        b.push_numbers(numbers.iter().copied());
+        b.into()
+    }
+
+    /// Encodes the numbers, so that at most `max_gap` instructions separate
+    /// consecutive outputs, e.g., to bound the latency between prints in an
+    /// interactive display. Transitions, that the heuristic can't encode within
+    /// the gap, are searched for with [`BfsEncoder::encode_within`]. Returns
+    /// `None`, if a transition can't be encoded within the gap.
+    #[must_use]
+    pub fn encode_numbers_bounded_gap(numbers: &[Acc], max_gap: u32) -> Option<Vec<Inst>> {
+        let max_gap = max_gap as usize;
         let mut b = Builder::new(Acc::new());
-        b.push_numbers(ir.iter().copied());
+        let mut enc = BfsEncoder::new();
+        for &n in numbers {
+            if heuristic_len(b.acc(), n, b.width()) <= max_gap {
+                b.push_number(n);
+            } else {
+                let path = enc.encode_within(b.acc(), n, max_gap)?;
+                b.append(&path);
+                b.push(Inst::O);
+            }
+        }
+        Some(b.into())
+    }
+
+    /// Encodes the characters of `s` with the fewest squares, at the expense
+    /// of a longer program.
+    #[must_use]
+    #[inline]
+    pub fn encode_str_min_squares(s: &str) -> Vec<Inst> {
+        let mut b = Builder::new(Acc::new());
+        b.push_string_min_squares(s);
         b.into()
     }
 
+    /// Encodes the numbers from `start` to `end`, inclusive, in ascending or
+    /// descending order. Consecutive numbers are reached by a single `i` or
+    /// `d`. Returns `None`, if the range contains 256 or -1, since those reset
+    /// to 0 and can't be printed.
+    #[must_use]
+    pub fn encode_range(start: i32, end: i32) -> Option<Vec<Inst>> {
+        let range = start.min(end)..=start.max(end);
+        if range.contains(&256) || range.contains(&-1) {
+            return None;
+        }
+        let mut b = Builder::new(Acc::new());
+        if start <= end {
+            b.push_numbers(range.map(Acc::from));
+        } else {
+            b.push_numbers(range.rev().map(Acc::from));
+        }
+        Some(b.into())
+    }
+
     #[must_use]
     #[inline]
     pub fn minimize(insts: &[Inst]) -> Vec<Inst> {
@@ -56,20 +398,163 @@ impl Inst {
         Self::encode_numbers(&numbers)
     }
 
+    /// Finds the single instruction to insert, and where, that most shortens
+    /// the program after [`Inst::minimize`]. An insertion that keeps the same
+    /// output can't shorten it, so this suggests a nearby output that's
+    /// cheaper to encode, e.g., for golfing when the exact numbers are
+    /// flexible. The earliest is chosen for ties. This runs a minimization for
+    /// each of the `4 * (insts.len() + 1)` candidates.
+    #[must_use]
+    pub fn suggest_insertion(insts: &[Inst]) -> Option<(usize, Inst)> {
+        let mut best = None;
+        let mut best_len = Inst::minimize(insts).len();
+        let mut candidate = Vec::with_capacity(insts.len() + 1);
+        for i in 0..=insts.len() {
+            for inst in [Inst::I, Inst::D, Inst::S, Inst::O] {
+                candidate.clear();
+                candidate.extend_from_slice(&insts[..i]);
+                candidate.push(inst);
+                candidate.extend_from_slice(&insts[i..]);
+                let len = Inst::minimize(&candidate).len();
+                if len < best_len {
+                    best = Some((i, inst));
+                    best_len = len;
+                }
+            }
+        }
+        best
+    }
+
+    /// Minimizes each program, like [`Inst::minimize`], but encodes each
+    /// transition shared between programs only once.
+    #[must_use]
+    pub fn minimize_batch(programs: &[Vec<Inst>]) -> Vec<Vec<Inst>> {
+        let mut enc = CachingEncoder::new();
+        programs
+            .iter()
+            .map(|insts| enc.encode_numbers(&Inst::eval_numbers(insts).0))
+            .collect()
+    }
+
+    /// Computes the lengths of the instructions before and after
+    /// [`Inst::minimize`].
+    #[must_use]
+    #[inline]
+    pub fn minimization_savings(insts: &[Inst]) -> (usize, usize) {
+        (insts.len(), Inst::minimize(insts).len())
+    }
+
+    /// Edits the instructions to output `target` instead. Segments, as split
+    /// by [`Inst::segments`], are kept verbatim when they still output the
+    /// target number from the new accumulator, and otherwise re-encoded, so a
+    /// change usually re-encodes its segment and the one following it.
+    /// Instructions after the last output are dropped.
+    #[must_use]
+    pub fn retarget(insts: &[Inst], target: &[Acc]) -> Vec<Inst> {
+        let segments = Inst::segments(insts);
+        let mut b = Builder::new(Acc::new());
+        for (i, &n) in target.iter().enumerate() {
+            match segments.get(i) {
+                Some(&(_, _, segment)) if Inst::eval(segment, b.acc()) == n => b.append(segment),
+                _ => b.push_number(n),
+            }
+        }
+        b.into()
+    }
+
+    /// Reconstructs the instructions, which step the accumulator through each
+    /// value in `trajectory`, or `None` if a step can't be made with a single
+    /// `i`, `d`, or `s`.
+    #[must_use]
+    pub fn from_trajectory(trajectory: &[Acc]) -> Option<Vec<Inst>> {
+        trajectory
+            .windows(2)
+            .map(|step| {
+                [Inst::I, Inst::D, Inst::S]
+                    .into_iter()
+                    .find(|&inst| step[0].apply(inst) == step[1])
+            })
+            .collect()
+    }
+
     #[must_use]
     pub fn parse<B: AsRef<[u8]>>(src: B) -> Vec<Inst> {
         let src = src.as_ref();
         let mut insts = Vec::with_capacity(src.len());
-        for c in src {
-            insts.push(match c {
-                b'i' => Inst::I,
-                b'd' => Inst::D,
-                b's' => Inst::S,
-                b'o' => Inst::O,
-                _ => Inst::Blank,
+        for &c in src {
+            insts.push(Inst::from_byte(c));
+        }
+        insts
+    }
+
+    /// Parses an annotated program, where `#` starts a comment to the end of
+    /// the line. Comments are returned separately from the instructions, at
+    /// their positions, so tools can round-trip commented source. The line
+    /// feed ending a comment is kept as a blank. [`Inst::parse`] treats `#`
+    /// as a blank.
+    #[must_use]
+    pub fn parse_annotated(src: &str) -> (Vec<Inst>, Vec<Comment>) {
+        let mut insts = Vec::with_capacity(src.len());
+        let mut comments = Vec::new();
+        let mut rest = src;
+        while let Some(start) = rest.find('#') {
+            insts.extend(rest[..start].bytes().map(Inst::from_byte));
+            let line = &rest[start + 1..];
+            let end = line.find('\n').unwrap_or(line.len());
+            comments.push(Comment {
+                index: insts.len(),
+                text: line[..end].to_owned(),
             });
+            rest = &line[end..];
+        }
+        insts.extend(rest.bytes().map(Inst::from_byte));
+        (insts, comments)
+    }
+
+    /// Checks that the instructions contain only `i`, `d`, `s`, and `o`, or
+    /// returns the index of the first blank.
+    ///
+    /// # Errors
+    ///
+    /// Returns the index of the first blank, if there is one.
+    pub fn validate_strict(insts: &[Inst]) -> Result<(), usize> {
+        match insts.iter().position(|&inst| inst == Inst::Blank) {
+            Some(i) => Err(i),
+            None => Ok(()),
+        }
+    }
+
+    /// Parses a dialect, which uses other bytes for commands.
+    #[must_use]
+    pub fn parse_with_alphabet<B: AsRef<[u8]>>(src: B, alphabet: &InstAlphabet) -> Vec<Inst> {
+        src.as_ref().iter().map(|&c| alphabet.get(c)).collect()
+    }
+
+    /// Parses a single command, where any unrecognized byte is a blank.
+    #[must_use]
+    #[inline]
+    pub const fn from_byte(c: u8) -> Inst {
+        match c {
+            b'i' => Inst::I,
+            b'd' => Inst::D,
+            b's' => Inst::S,
+            b'o' => Inst::O,
+            _ => Inst::Blank,
         }
+    }
+
+    /// Lays out the instructions as characters in rows of `width`, with
+    /// blanks as spaces. The last row may be shorter.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `width` is 0.
+    #[must_use]
+    pub fn to_grid(insts: &[Inst], width: usize) -> Vec<Vec<char>> {
         insts
+            .chunks(width)
+            .map(|row| row.iter().map(|inst| inst.as_char()).collect())
+            .collect()
     }
 
     #[must_use]
@@ -85,6 +570,223 @@ impl Inst {
         (numbers, acc)
     }
 
+    /// Trims the instructions after the last output or blank, which don't
+    /// affect the output. Trailing blanks are kept, since they are printed in
+    /// the transcript.
+    #[must_use]
+    pub fn trim_trailing_noops(insts: &[Inst]) -> &[Inst] {
+        match insts
+            .iter()
+            .rposition(|&inst| inst == Inst::O || inst == Inst::Blank)
+        {
+            Some(i) => &insts[..=i],
+            None => &[],
+        }
+    }
+
+    /// Pairs each instruction with the accumulator after it's applied.
+    #[must_use]
+    pub fn annotate(insts: &[Inst]) -> Vec<(Inst, Acc)> {
+        insts
+            .iter()
+            .scan(Acc::new(), |acc, &inst| {
+                *acc = acc.apply(inst);
+                Some((inst, *acc))
+            })
+            .collect()
+    }
+
+    /// Evaluates the instructions until the output at `index` and returns it,
+    /// or `None`, if there are fewer outputs.
+    #[must_use]
+    pub fn output_at(insts: &[Inst], index: usize) -> Option<Acc> {
+        let mut acc = Acc::new();
+        let mut count = 0;
+        for &inst in insts {
+            match inst {
+                Inst::O if count == index => return Some(acc),
+                Inst::O => count += 1,
+                _ => acc = acc.apply(inst),
+            }
+        }
+        None
+    }
+
+    /// Splits the instructions at each output into segments of the
+    /// accumulator before, the number output, and the instructions up to and
+    /// including the `o`. Instructions after the last output are excluded.
+    #[must_use]
+    pub fn segments(insts: &[Inst]) -> Vec<(Acc, Acc, &[Inst])> {
+        let mut segments = Vec::new();
+        let mut acc = Acc::new();
+        let mut from = acc;
+        let mut start = 0;
+        for (i, &inst) in insts.iter().enumerate() {
+            match inst {
+                Inst::O => {
+                    segments.push((from, acc, &insts[start..=i]));
+                    from = acc;
+                    start = i + 1;
+                }
+                _ => acc = acc.apply(inst),
+            }
+        }
+        segments
+    }
+
+    /// Counts the number of times each number is output, regardless of order.
+    #[must_use]
+    pub fn output_multiset(insts: &[Inst]) -> HashMap<Acc, usize> {
+        let mut counts = HashMap::new();
+        let mut acc = Acc::new();
+        for &inst in insts {
+            match inst {
+                Inst::O => *counts.entry(acc).or_insert(0) += 1,
+                _ => acc = acc.apply(inst),
+            }
+        }
+        counts
+    }
+
+    /// Computes the Shannon entropy, in bits, of the distribution of numbers
+    /// output. A program with no output has an entropy of 0.
+    #[must_use]
+    pub fn output_entropy(insts: &[Inst]) -> f64 {
+        let counts = Inst::output_multiset(insts);
+        let total = counts.values().sum::<usize>() as f64;
+        counts
+            .values()
+            .map(|&count| {
+                let p = count as f64 / total;
+                -p * p.log2()
+            })
+            .sum()
+    }
+
+    /// Computes the minimum and maximum accumulator attained while running the
+    /// program from 0, compared as signed, like Deadfish prints them.
+    #[must_use]
+    pub fn acc_range(insts: &[Inst]) -> (Acc, Acc) {
+        let mut acc = Acc::new();
+        let (mut min, mut max) = (acc, acc);
+        for &inst in insts {
+            acc = acc.apply(inst);
+            if (acc.value() as i32) < (min.value() as i32) {
+                min = acc;
+            } else if (acc.value() as i32) > (max.value() as i32) {
+                max = acc;
+            }
+        }
+        (min, max)
+    }
+
+    /// Computes the numbers printed by both programs, sorted and without
+    /// duplicates.
+    #[must_use]
+    pub fn common_outputs(a: &[Inst], b: &[Inst]) -> Vec<Acc> {
+        let (mut a, _) = Inst::eval_numbers(a);
+        let (b, _) = Inst::eval_numbers(b);
+        let b: HashSet<Acc> = b.into_iter().collect();
+        a.sort();
+        a.dedup();
+        a.retain(|n| b.contains(n));
+        a
+    }
+
+    /// Computes the Levenshtein distance between two programs, i.e., the
+    /// fewest instructions inserted, deleted, or substituted to change `a` to
+    /// `b`. This compares the programs themselves, not their outputs.
+    #[must_use]
+    pub fn edit_distance(a: &[Inst], b: &[Inst]) -> usize {
+        let mut prev: Vec<usize> = (0..=b.len()).collect();
+        let mut curr = vec![0; b.len() + 1];
+        for (i, &x) in a.iter().enumerate() {
+            curr[0] = i + 1;
+            for (j, &y) in b.iter().enumerate() {
+                let substitute = prev[j] + usize::from(x != y);
+                curr[j + 1] = substitute.min(prev[j + 1] + 1).min(curr[j] + 1);
+            }
+            mem::swap(&mut prev, &mut curr);
+        }
+        prev[b.len()]
+    }
+
+    /// Checks whether two programs produce the same output, either as
+    /// numbers or as the literal transcript, depending on `mode`.
+    #[must_use]
+    pub fn output_equivalent(a: &[Inst], b: &[Inst], mode: EquivalenceMode) -> bool {
+        match mode {
+            EquivalenceMode::NumbersOnly => Inst::eval_numbers(a).0 == Inst::eval_numbers(b).0,
+            EquivalenceMode::FullTranscript => Inst::output_bytes(a).eq(Inst::output_bytes(b)),
+        }
+    }
+
+    /// Finds the index of the first printed number that differs from
+    /// `expected`. Returns `None`, if they match up to the shorter of the two,
+    /// so compare the lengths separately when they matter.
+    #[must_use]
+    pub fn first_divergence(insts: &[Inst], expected: &[Acc]) -> Option<usize> {
+        let mut acc = Acc::new();
+        let outputs = insts.iter().filter_map(|&inst| {
+            acc = acc.apply(inst);
+            (inst == Inst::O).then_some(acc)
+        });
+        outputs
+            .zip(expected)
+            .position(|(n, &expected)| n != expected)
+    }
+
+    /// Checks whether the program prints any number, rather than only prompts
+    /// and blanks.
+    #[must_use]
+    #[inline]
+    pub fn has_output(insts: &[Inst]) -> bool {
+        insts.contains(&Inst::O)
+    }
+
+    /// Counts the numbers the program prints.
+    #[must_use]
+    #[inline]
+    pub fn output_count(insts: &[Inst]) -> usize {
+        insts.iter().filter(|&&inst| inst == Inst::O).count()
+    }
+
+    /// Counts the outputs that print the same number as the previous output,
+    /// or 0 for the first output, so need no change to the accumulator.
+    #[must_use]
+    pub fn free_output_count(insts: &[Inst]) -> usize {
+        let (numbers, _) = Inst::eval_numbers(insts);
+        let mut prev = Acc::new();
+        let mut count = 0;
+        for n in numbers {
+            if n == prev {
+                count += 1;
+            }
+            prev = n;
+        }
+        count
+    }
+
+    /// Finds the longest run of consecutive identical numbers output and its
+    /// length. The first is chosen for ties and `(0, 0)` without output.
+    #[must_use]
+    pub fn max_output_run(insts: &[Inst]) -> (Acc, usize) {
+        let (numbers, _) = Inst::eval_numbers(insts);
+        let mut max = (Acc::new(), 0);
+        let mut len = 0;
+        for (i, &n) in numbers.iter().enumerate() {
+            if i != 0 && numbers[i - 1] == n {
+                len += 1;
+            } else {
+                len = 1;
+            }
+            if len > max.1 {
+                max = (n, len);
+            }
+        }
+        max
+    }
+
     #[must_use]
     pub fn eval_string(insts: &[Inst]) -> Option<String> {
         let mut s = String::new();
@@ -98,18 +800,270 @@ impl Inst {
         Some(s)
     }
 
-    pub fn interpret<W: Write>(insts: &[Inst], stdout: &mut W) -> io::Result<()> {
+    /// Computes the number of bytes that [`Inst::interpret`] would write,
+    /// without rendering it.
+    #[must_use]
+    pub fn output_byte_len(insts: &[Inst]) -> usize {
+        let mut len = 0;
         let mut acc = Acc::new();
         for &inst in insts {
+            len += ">> ".len();
+            match inst {
+                Inst::I | Inst::D | Inst::S => acc = acc.apply(inst),
+                Inst::O => len += acc.decimal_len() + 1,
+                Inst::Blank => len += 1,
+            }
+        }
+        len
+    }
+
+    /// Lazily yields the bytes that [`Inst::interpret`] would write.
+    pub fn output_bytes(insts: &[Inst]) -> impl Iterator<Item = u8> + '_ {
+        insts
+            .iter()
+            .scan(Acc::new(), |acc, &inst| {
+                // Long enough for a prompt and `-2147483648\n`
+                let mut line = [0; 15];
+                line[..3].copy_from_slice(b">> ");
+                let len = match inst {
+                    Inst::I | Inst::D | Inst::S => {
+                        *acc = acc.apply(inst);
+                        3
+                    }
+                    Inst::O => {
+                        let mut rest = &mut line[3..];
+                        writeln!(rest, "{acc}").unwrap();
+                        15 - rest.len()
+                    }
+                    Inst::Blank => {
+                        line[3] = b'\n';
+                        4
+                    }
+                };
+                Some(line.into_iter().take(len))
+            })
+            .flatten()
+    }
+
+    pub fn interpret<W: Write>(insts: &[Inst], stdout: &mut W) -> io::Result<()> {
+        Inst::interpret_from(insts, Acc::new(), stdout)
+    }
+
+    /// Interprets the instructions like [`Inst::interpret`] and returns the
+    /// shell transcript.
+    ///
+    /// # Panics
+    ///
+    /// Never panics, since writing to a `Vec` can't fail and the transcript is
+    /// always ASCII.
+    #[must_use]
+    pub fn interpret_to_string(insts: &[Inst]) -> String {
+        let mut stdout = Vec::with_capacity(Inst::output_byte_len(insts));
+        Inst::interpret(insts, &mut stdout).expect("writing to a Vec can't fail");
+        String::from_utf8(stdout).expect("transcript is only prompts, numbers, and line feeds")
+    }
+
+    /// Interprets the instructions, starting with an accumulator of `acc`,
+    /// such as when resuming a session.
+    ///
+    /// # Errors
+    ///
+    /// Returns any error from writing to `stdout`.
+    pub fn interpret_from<W: Write>(insts: &[Inst], acc: Acc, stdout: &mut W) -> io::Result<()> {
+        Inst::interpret_inner(insts, acc, stdout, usize::MAX)?;
+        Ok(())
+    }
+
+    /// Interprets the instructions like [`Inst::interpret`], but, as a
+    /// learning aid, follows each output with a `#` comment showing how the
+    /// accumulator got there from the previous output, e.g.,
+    /// `# 0 -> 2 by ii (2 increments)`.
+    ///
+    /// # Errors
+    ///
+    /// Returns any error from writing to `stdout`.
+    pub fn interpret_verbose<W: Write>(insts: &[Inst], stdout: &mut W) -> io::Result<()> {
+        let mut acc = Acc::new();
+        let mut from = acc;
+        let mut start = 0;
+        for (i, &inst) in insts.iter().enumerate() {
             write!(stdout, ">> ")?;
             match inst {
                 Inst::I | Inst::D | Inst::S => acc = acc.apply(inst),
-                Inst::O => writeln!(stdout, "{acc}")?,
+                Inst::O => {
+                    writeln!(stdout, "{acc}")?;
+                    let segment = &insts[start..i];
+                    if segment.iter().all(|&inst| inst == Inst::Blank) {
+                        writeln!(stdout, "# {acc} again")?;
+                    } else {
+                        let ops = segment
+                            .iter()
+                            .map(|inst| inst.as_char())
+                            .collect::<String>();
+                        let mut counts = Vec::new();
+                        for (inst, name) in [
+                            (Inst::I, "increment"),
+                            (Inst::D, "decrement"),
+                            (Inst::S, "square"),
+                        ] {
+                            let count = segment.iter().filter(|&&i| i == inst).count();
+                            match count {
+                                0 => {}
+                                1 => counts.push(format!("1 {name}")),
+                                _ => counts.push(format!("{count} {name}s")),
+                            }
+                        }
+                        let counts = counts.join(", ");
+                        writeln!(stdout, "# {from} -> {acc} by {ops} ({counts})")?;
+                    }
+                    from = acc;
+                    start = i + 1;
+                }
                 Inst::Blank => writeln!(stdout)?,
             }
         }
         stdout.flush()
     }
+
+    /// Interprets at most `max_insts` instructions, including blanks. Returns
+    /// whether the program ran to completion within the limit.
+    ///
+    /// # Errors
+    ///
+    /// Returns any error from writing to `stdout`.
+    pub fn interpret_limited<W: Write>(
+        insts: &[Inst],
+        stdout: &mut W,
+        max_insts: usize,
+    ) -> io::Result<bool> {
+        Inst::interpret_inner(insts, Acc::new(), stdout, max_insts)
+    }
+
+    /// Interprets raw input as it would appear when typed into the reference
+    /// interpreter in a terminal, with each line echoed after the prompt
+    /// it's typed at.
+    ///
+    /// The reference prints `">> "` before reading each byte with `getchar`.
+    /// Input is line-buffered by the terminal, so a line is echoed once it's
+    /// entered, then every byte in it, including the line feed, is run, each
+    /// followed by the prompt for the next byte. The line feed is an
+    /// unrecognized command, so prints a blank line. The reference loops
+    /// forever at the end of input, but this stops after the last prompt.
+    ///
+    /// # Errors
+    ///
+    /// Returns any error from reading `input` or writing to `stdout`.
+    pub fn interactive_session<R: Read, W: Write>(input: R, stdout: &mut W) -> io::Result<()> {
+        let mut input = BufReader::new(input);
+        let mut line = Vec::new();
+        let mut acc = Acc::new();
+        write!(stdout, ">> ")?;
+        loop {
+            line.clear();
+            if input.read_until(b'\n', &mut line)? == 0 {
+                break;
+            }
+            stdout.write_all(&line)?;
+            for &c in &line {
+                match Inst::from_byte(c) {
+                    Inst::O => writeln!(stdout, "{acc}")?,
+                    Inst::Blank => writeln!(stdout)?,
+                    inst => acc = acc.apply(inst),
+                }
+                write!(stdout, ">> ")?;
+            }
+        }
+        stdout.flush()
+    }
+
+    fn interpret_inner<W: Write>(
+        insts: &[Inst],
+        acc: Acc,
+        stdout: &mut W,
+        max_insts: usize,
+    ) -> io::Result<bool> {
+        let mut acc = acc;
+        for &inst in insts.iter().take(max_insts) {
+            write!(stdout, ">> ")?;
+            match inst {
+                Inst::I | Inst::D | Inst::S => acc = acc.apply(inst),
+                Inst::O => writeln!(stdout, "{acc}")?,
+                Inst::Blank => writeln!(stdout)?,
+            }
+        }
+        stdout.flush()?;
+        Ok(insts.len() <= max_insts)
+    }
+}
+
+/// A comment in an annotated program, as parsed by [`Inst::parse_annotated`].
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Comment {
+    /// Number of instructions before the comment.
+    pub index: usize,
+    /// Text after the `#`, up to the end of the line.
+    pub text: String,
+}
+
+/// What [`Inst::output_equivalent`] compares.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum EquivalenceMode {
+    /// Only the printed numbers, ignoring prompts and blanks.
+    #[default]
+    NumbersOnly,
+    /// The complete transcript of [`Inst::interpret`], including prompts and
+    /// blank lines.
+    FullTranscript,
+}
+
+/// The bytes accepted for each command when parsing. Any other byte is a
+/// blank.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct InstAlphabet {
+    table: [Inst; 256],
+}
+
+impl InstAlphabet {
+    /// Creates an alphabet, which accepts the given bytes for `i`, `d`, `s`,
+    /// and `o`, respectively. A byte in multiple sets is the last command.
+    #[must_use]
+    pub fn new(i: &[u8], d: &[u8], s: &[u8], o: &[u8]) -> Self {
+        let mut table = [Inst::Blank; 256];
+        for (inst, bytes) in [(Inst::I, i), (Inst::D, d), (Inst::S, s), (Inst::O, o)] {
+            for &c in bytes {
+                table[c as usize] = inst;
+            }
+        }
+        InstAlphabet { table }
+    }
+
+    /// Creates an alphabet of `+`, `-`, `*`, and `.`.
+    #[must_use]
+    #[inline]
+    pub fn symbolic() -> Self {
+        InstAlphabet::new(b"+", b"-", b"*", b".")
+    }
+
+    /// Creates an alphabet of the reference commands in either case.
+    #[must_use]
+    #[inline]
+    pub fn case_insensitive() -> Self {
+        InstAlphabet::new(b"iI", b"dD", b"sS", b"oO")
+    }
+
+    #[must_use]
+    #[inline]
+    pub const fn get(&self, c: u8) -> Inst {
+        self.table[c as usize]
+    }
+}
+
+impl Default for InstAlphabet {
+    /// The reference commands `i`, `d`, `s`, and `o`.
+    #[inline]
+    fn default() -> Self {
+        InstAlphabet::new(b"i", b"d", b"s", b"o")
+    }
 }
 
 /// Deadfish intermediate representation.
@@ -119,11 +1073,29 @@ pub enum Ir {
     Number(Acc),
     /// Print `">> "` shell prompts.
     Prompts(u32),
-    /// Print line feeds.
+    /// Print line feeds for unrecognized commands, each after a `">> "` shell
+    /// prompt.
     Blanks(u32),
 }
 
 impl Ir {
+    /// Constructs the transcript of the heuristic encoding of the numbers,
+    /// like `Ir::eval(&Inst::encode_numbers(numbers))`, but without encoding
+    /// the instructions.
+    #[must_use]
+    pub fn from_numbers(numbers: &[Acc]) -> Vec<Self> {
+        let mut ir = Vec::with_capacity(numbers.len() * 2);
+        let mut acc = Acc::new();
+        for &n in numbers {
+            let len = heuristic_len(acc, n, Width::default());
+            // Including a prompt for `o`
+            ir.push(Ir::Prompts(len as u32 + 1));
+            ir.push(Ir::Number(n));
+            acc = n;
+        }
+        ir
+    }
+
     #[must_use]
     pub fn eval(insts: &[Inst]) -> (Vec<Self>, Acc) {
         let mut ir = Vec::new();
@@ -183,6 +1155,18 @@ impl Ir {
         (ir, acc)
     }
 
+    /// Appends the blank that the reference interpreter prints at the end of
+    /// input, like [`Builder::finish_with_eof_prompt`], merging it with any
+    /// trailing blanks.
+    ///
+    /// [`Builder::finish_with_eof_prompt`]: crate::Builder::finish_with_eof_prompt
+    pub fn finish_with_eof_prompt(ir: &mut Vec<Ir>) {
+        match ir.last_mut() {
+            Some(Ir::Blanks(count)) => *count += 1,
+            _ => ir.push(Ir::Blanks(1)),
+        }
+    }
+
     #[must_use]
     pub fn eval_string(ir: &[Ir]) -> Option<String> {
         let mut s = String::new();
@@ -205,7 +1189,7 @@ impl Ir {
                 }
                 Ir::Blanks(count) => {
                     for _ in 0..count {
-                        writeln!(stdout)?;
+                        writeln!(stdout, ">> ")?;
                     }
                 }
             }
@@ -213,3 +1197,153 @@ impl Ir {
         stdout.flush()
     }
 }
+
+/// A single event of an [`Ir`] or [`Inst`] program.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum IrEvent {
+    /// Output a number and a line feed.
+    Number(Acc),
+    /// Print a `">> "` shell prompt.
+    Prompt,
+    /// Print a line feed.
+    Blank,
+    /// The accumulator was reset to 0 from 256 or -1. It prints nothing and
+    /// is only produced by [`InstCursor`], since `Ir` does not track the
+    /// accumulator.
+    Reset,
+}
+
+/// Steps through an [`Ir`] program one event at a time, expanding
+/// `Ir::Prompts` and `Ir::Blanks` into individual events. Each blank is a
+/// prompt followed by a line feed.
+#[derive(Clone, Debug)]
+pub struct IrCursor<'a> {
+    ir: &'a [Ir],
+    index: usize,
+    count: u32,
+}
+
+impl<'a> IrCursor<'a> {
+    #[must_use]
+    #[inline]
+    pub fn new(ir: &'a [Ir]) -> Self {
+        Self::with_position(ir, (0, 0))
+    }
+
+    /// Resumes from a position saved by [`IrCursor::position`].
+    #[must_use]
+    #[inline]
+    pub fn with_position(ir: &'a [Ir], position: (usize, u32)) -> Self {
+        IrCursor {
+            ir,
+            index: position.0,
+            count: position.1,
+        }
+    }
+
+    /// Returns the index of the current `Ir` and the number of events already
+    /// consumed from it.
+    #[must_use]
+    #[inline]
+    pub fn position(&self) -> (usize, u32) {
+        (self.index, self.count)
+    }
+}
+
+impl Iterator for IrCursor<'_> {
+    type Item = IrEvent;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let (event, len) = match *self.ir.get(self.index)? {
+                Ir::Number(n) => (IrEvent::Number(n), 1),
+                Ir::Prompts(count) => (IrEvent::Prompt, count),
+                Ir::Blanks(count) if self.count % 2 == 0 => (IrEvent::Prompt, 2 * count),
+                Ir::Blanks(count) => (IrEvent::Blank, 2 * count),
+            };
+            if self.count < len {
+                self.count += 1;
+                return Some(event);
+            }
+            self.index += 1;
+            self.count = 0;
+        }
+    }
+}
+
+/// Steps through an [`Inst`] program one event at a time, like [`IrCursor`],
+/// but also reports when the accumulator is reset.
+#[derive(Clone, Debug)]
+pub struct InstCursor<'a> {
+    insts: &'a [Inst],
+    index: usize,
+    acc: Acc,
+    prompted: bool,
+}
+
+impl<'a> InstCursor<'a> {
+    #[must_use]
+    #[inline]
+    pub fn new(insts: &'a [Inst]) -> Self {
+        InstCursor {
+            insts,
+            index: 0,
+            acc: Acc::new(),
+            prompted: false,
+        }
+    }
+
+    /// Returns the accumulator after the instructions stepped through so far.
+    #[must_use]
+    #[inline]
+    pub fn acc(&self) -> Acc {
+        self.acc
+    }
+}
+
+impl Iterator for InstCursor<'_> {
+    type Item = IrEvent;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let inst = *self.insts.get(self.index)?;
+            if !self.prompted {
+                self.prompted = true;
+                return Some(IrEvent::Prompt);
+            }
+            self.index += 1;
+            self.prompted = false;
+            match inst {
+                Inst::O => return Some(IrEvent::Number(self.acc)),
+                Inst::Blank => return Some(IrEvent::Blank),
+                _ => {
+                    let (acc, reset) = self.acc.apply_checked(inst);
+                    self.acc = acc;
+                    if reset {
+                        return Some(IrEvent::Reset);
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl Display for Ir {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match *self {
+            Ir::Number(n) => write!(f, "{n}"),
+            Ir::Prompts(count) => {
+                for _ in 0..count {
+                    write!(f, ">> ")?;
+                }
+                Ok(())
+            }
+            Ir::Blanks(count) => {
+                for _ in 0..count {
+                    writeln!(f, ">> ")?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
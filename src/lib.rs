@@ -47,6 +47,7 @@
     clippy::cast_lossless,
     clippy::cast_possible_truncation,
     clippy::cast_possible_wrap,
+    clippy::cast_precision_loss,
     clippy::cast_sign_loss,
     clippy::module_name_repetitions
 )]
@@ -54,14 +55,31 @@
 pub use acc::*;
 pub use bfs::*;
 pub use builder::*;
+pub use caching::*;
+pub(crate) use golf::GOLF_CONSTANTS;
 pub(crate) use heuristic::*;
+pub use heuristic::HeuristicConfig;
 pub use inst::*;
+pub use program::*;
+#[cfg(feature = "rand")]
+pub use random::*;
+pub use route::*;
+pub use stream::*;
+pub use value::*;
 
 mod acc;
 mod bfs;
 mod builder;
+mod caching;
+mod golf;
 mod heuristic;
 mod inst;
+mod program;
+#[cfg(feature = "rand")]
+mod random;
+mod route;
+mod stream;
+mod value;
 
 #[cfg(test)]
 mod tests;
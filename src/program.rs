@@ -0,0 +1,180 @@
+// Copyright (C) 2022 Andrew Archibald
+//
+// deadfish is free software: you can redistribute it and/or modify it under the
+// terms of the GNU Lesser General Public License as published by the Free
+// Software Foundation, either version 3 of the License, or (at your option) any
+// later version. You should have received a copy of the GNU Lesser General
+// Public License along with deadfish. If not, see http://www.gnu.org/licenses/.
+
+use std::borrow::Borrow;
+use std::io::{self, Write};
+use std::ops::Deref;
+
+use crate::{Acc, Inst, Ir};
+
+/// A borrowed Deadfish program, which provides the associated functions of
+/// [`Inst`] as methods. It's to [`Program`] as `str` is to `String`.
+#[repr(transparent)]
+#[derive(Debug, PartialEq, Eq, Hash)]
+pub struct Insts([Inst]);
+
+/// An owned Deadfish program, which dereferences to [`Insts`].
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Program(Vec<Inst>);
+
+impl Insts {
+    #[must_use]
+    #[inline]
+    pub fn from_slice(insts: &[Inst]) -> &Self {
+        // SAFETY: `Insts` is a `repr(transparent)` wrapper of `[Inst]`.
+        unsafe { &*(insts as *const [Inst] as *const Insts) }
+    }
+
+    #[must_use]
+    #[inline]
+    pub const fn as_slice(&self) -> &[Inst] {
+        &self.0
+    }
+
+    #[must_use]
+    #[inline]
+    pub fn eval(&self, acc: Acc) -> Acc {
+        Inst::eval(&self.0, acc)
+    }
+
+    #[must_use]
+    #[inline]
+    pub fn eval_numbers(&self) -> (Vec<Acc>, Acc) {
+        Inst::eval_numbers(&self.0)
+    }
+
+    #[must_use]
+    #[inline]
+    pub fn eval_string(&self) -> Option<String> {
+        Inst::eval_string(&self.0)
+    }
+
+    #[must_use]
+    #[inline]
+    pub fn eval_ir(&self) -> (Vec<Ir>, Acc) {
+        Ir::eval(&self.0)
+    }
+
+    #[must_use]
+    #[inline]
+    pub fn minimize(&self) -> Program {
+        Inst::minimize(&self.0).into()
+    }
+
+    /// Interprets the program like [`Inst::interpret`].
+    ///
+    /// # Errors
+    ///
+    /// Returns any error from writing to `stdout`.
+    #[inline]
+    pub fn interpret<W: Write>(&self, stdout: &mut W) -> io::Result<()> {
+        Inst::interpret(&self.0, stdout)
+    }
+}
+
+impl Program {
+    #[must_use]
+    #[inline]
+    pub const fn new() -> Self {
+        Program(Vec::new())
+    }
+
+    #[must_use]
+    #[inline]
+    pub fn parse<B: AsRef<[u8]>>(src: B) -> Self {
+        Program(Inst::parse(src))
+    }
+
+    #[must_use]
+    #[inline]
+    pub fn as_insts(&self) -> &Insts {
+        Insts::from_slice(&self.0)
+    }
+
+    #[must_use]
+    #[inline]
+    pub fn into_vec(self) -> Vec<Inst> {
+        self.0
+    }
+}
+
+impl Deref for Insts {
+    type Target = [Inst];
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl Deref for Program {
+    type Target = Insts;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        self.as_insts()
+    }
+}
+
+impl Borrow<Insts> for Program {
+    #[inline]
+    fn borrow(&self) -> &Insts {
+        self.as_insts()
+    }
+}
+
+impl ToOwned for Insts {
+    type Owned = Program;
+
+    #[inline]
+    fn to_owned(&self) -> Self::Owned {
+        Program(self.0.to_vec())
+    }
+}
+
+impl AsRef<[Inst]> for Insts {
+    #[inline]
+    fn as_ref(&self) -> &[Inst] {
+        &self.0
+    }
+}
+
+impl AsRef<[Inst]> for Program {
+    #[inline]
+    fn as_ref(&self) -> &[Inst] {
+        &self.0
+    }
+}
+
+impl<'a> From<&'a [Inst]> for &'a Insts {
+    #[inline]
+    fn from(insts: &'a [Inst]) -> Self {
+        Insts::from_slice(insts)
+    }
+}
+
+impl From<Vec<Inst>> for Program {
+    #[inline]
+    fn from(insts: Vec<Inst>) -> Self {
+        Program(insts)
+    }
+}
+
+impl From<&Insts> for Program {
+    #[inline]
+    fn from(insts: &Insts) -> Self {
+        insts.to_owned()
+    }
+}
+
+impl From<Program> for Vec<Inst> {
+    #[inline]
+    fn from(program: Program) -> Self {
+        program.0
+    }
+}
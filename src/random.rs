@@ -0,0 +1,40 @@
+// Copyright (C) 2022 Andrew Archibald
+//
+// deadfish is free software: you can redistribute it and/or modify it under the
+// terms of the GNU Lesser General Public License as published by the Free
+// Software Foundation, either version 3 of the License, or (at your option) any
+// later version. You should have received a copy of the GNU Lesser General
+// Public License along with deadfish. If not, see http://www.gnu.org/licenses/.
+
+use rand::distributions::{Distribution, WeightedIndex};
+use rand::Rng;
+
+use crate::Inst;
+
+/// Default relative weights of `i`, `d`, `s`, and `o` for
+/// [`random_program`], which make outputs rarer than arithmetic.
+pub const DEFAULT_INST_WEIGHTS: [u32; 4] = [8, 8, 3, 2];
+
+/// Generates a random program of `len` instructions with the default weights.
+#[must_use]
+#[inline]
+pub fn random_program<R: Rng + ?Sized>(rng: &mut R, len: usize) -> Vec<Inst> {
+    random_program_weighted(rng, len, DEFAULT_INST_WEIGHTS)
+}
+
+/// Generates a random program of `len` instructions, choosing `i`, `d`, `s`,
+/// and `o` with the given relative weights.
+///
+/// # Panics
+///
+/// Panics if all weights are zero.
+#[must_use]
+pub fn random_program_weighted<R: Rng + ?Sized>(
+    rng: &mut R,
+    len: usize,
+    weights: [u32; 4],
+) -> Vec<Inst> {
+    const INSTS: [Inst; 4] = [Inst::I, Inst::D, Inst::S, Inst::O];
+    let dist = WeightedIndex::new(weights).expect("invalid instruction weights");
+    (0..len).map(|_| INSTS[dist.sample(rng)]).collect()
+}
@@ -0,0 +1,78 @@
+// Copyright (C) 2022 Andrew Archibald
+//
+// deadfish is free software: you can redistribute it and/or modify it under the
+// terms of the GNU Lesser General Public License as published by the Free
+// Software Foundation, either version 3 of the License, or (at your option) any
+// later version. You should have received a copy of the GNU Lesser General
+// Public License along with deadfish. If not, see http://www.gnu.org/licenses/.
+
+use crate::{Acc, Builder, Inst, Offset, Width};
+
+/// A transition between values as offsets interleaved with squares, which can
+/// be composed before lowering to instructions.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Route(Vec<RouteStep>);
+
+/// A step in a [`Route`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum RouteStep {
+    /// A run of `i` or `d`.
+    Offset(Offset),
+    /// `s`
+    Square,
+}
+
+impl Route {
+    #[must_use]
+    #[inline]
+    pub const fn new() -> Self {
+        Route(Vec::new())
+    }
+
+    #[inline]
+    pub fn push(&mut self, step: RouteStep) {
+        self.0.push(step);
+    }
+
+    /// Appends the steps of `other` to this route.
+    #[inline]
+    pub fn extend(&mut self, other: &Route) {
+        self.0.extend_from_slice(&other.0);
+    }
+
+    #[must_use]
+    #[inline]
+    pub fn steps(&self) -> &[RouteStep] {
+        &self.0
+    }
+
+    /// Computes the value reached by following the route from `acc`.
+    #[must_use]
+    pub fn apply(&self, acc: Acc) -> Acc {
+        let width = Width::default();
+        self.0.iter().fold(acc, |acc, &step| match step {
+            RouteStep::Offset(offset) => width.offset(acc, offset),
+            RouteStep::Square => width.apply(acc, Inst::S),
+        })
+    }
+
+    /// Lowers the route to instructions.
+    #[must_use]
+    pub fn to_insts(&self) -> Vec<Inst> {
+        let mut b = Builder::new(Acc::new());
+        for &step in &self.0 {
+            match step {
+                RouteStep::Offset(offset) => b.offset(offset),
+                RouteStep::Square => b.push(Inst::S),
+            }
+        }
+        b.into()
+    }
+}
+
+impl From<Vec<RouteStep>> for Route {
+    #[inline]
+    fn from(steps: Vec<RouteStep>) -> Self {
+        Route(steps)
+    }
+}
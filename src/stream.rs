@@ -0,0 +1,59 @@
+// Copyright (C) 2022 Andrew Archibald
+//
+// deadfish is free software: you can redistribute it and/or modify it under the
+// terms of the GNU Lesser General Public License as published by the Free
+// Software Foundation, either version 3 of the License, or (at your option) any
+// later version. You should have received a copy of the GNU Lesser General
+// Public License along with deadfish. If not, see http://www.gnu.org/licenses/.
+
+use crate::{Acc, Builder, Inst};
+
+/// Encodes numbers as they arrive, where each continues from the accumulator
+/// left by the previous, instead of from 0, like [`Inst::encode_numbers`] for
+/// the whole sequence.
+#[derive(Clone, Debug)]
+pub struct NumberStream {
+    builder: Builder,
+}
+
+impl NumberStream {
+    #[must_use]
+    #[inline]
+    pub fn new() -> Self {
+        NumberStream {
+            builder: Builder::new(Acc::new()),
+        }
+    }
+
+    /// Encodes `n`, continuing from the last number encoded.
+    #[inline]
+    pub fn encode(&mut self, n: Acc) {
+        self.builder.push_number(n);
+    }
+
+    /// Encodes each number in sequence.
+    #[inline]
+    pub fn encode_all(&mut self, numbers: &[Acc]) {
+        self.builder.push_numbers(numbers.iter().copied());
+    }
+
+    /// Returns the accumulator after the last number encoded.
+    #[must_use]
+    #[inline]
+    pub const fn acc(&self) -> Acc {
+        self.builder.acc()
+    }
+
+    /// Returns the whole program encoded so far.
+    #[must_use]
+    #[inline]
+    pub fn finish(self) -> Vec<Inst> {
+        self.builder.into()
+    }
+}
+
+impl Default for NumberStream {
+    fn default() -> Self {
+        Self::new()
+    }
+}
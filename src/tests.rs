@@ -6,6 +6,9 @@
 // later version. You should have received a copy of the GNU Lesser General
 // Public License along with deadfish. If not, see http://www.gnu.org/licenses/.
 
+use std::collections::HashMap;
+use std::fmt::Write;
+
 use crate::*;
 
 macro_rules! insts[
@@ -88,40 +91,67 @@ fn hello_world() {
 >> 
 ";
 
-    assert_eq!((ir, Acc::from(100)), Ir::eval(&program));
+    assert_eq!((ir.clone(), Acc::from(100)), Ir::eval(&program));
 
     assert_eq!(minimized, Inst::minimize(&program));
+    assert_eq!(minimized, Inst::minimize(&minimized));
+    let (original_len, minimized_len) = Inst::minimization_savings(&program);
+    assert_eq!(
+        (program.len(), minimized.len()),
+        (original_len, minimized_len)
+    );
+    assert!(minimized_len < original_len);
 
     let mut stdout = Vec::new();
     Inst::interpret(&program, &mut stdout).unwrap();
     assert_eq!(shell, String::from_utf8(stdout).unwrap());
+    assert_eq!(shell, Inst::interpret_to_string(&program));
+
+    let mut stdout = Vec::new();
+    Ir::interpret(&ir, &mut stdout).unwrap();
+    assert_eq!(shell, String::from_utf8(stdout).unwrap());
+
+    assert_eq!(shell.len(), Inst::output_byte_len(&program));
+    assert_eq!(
+        shell.as_bytes(),
+        Inst::output_bytes(&program).collect::<Vec<_>>()
+    );
 }
 
 #[test]
 fn compare_heuristic() {
-    compare_encode(box |acc, n| Some(Inst::encode_number(acc, n)))
+    compare_encode(false, box |acc, n| Some(Inst::encode_number(acc, n)))
 }
 
 #[test]
 fn compare_bfs() {
     let mut enc = BfsEncoder::with_bound(16);
-    compare_encode(box move |acc, n| {
-        let (mut path, optimal) = enc.encode(acc, n);
-        if let Some(path) = &mut path {
-            path.push(Inst::O);
-        }
-        if !optimal {
-            println!("{acc} -> {n} may not be optimal with {path:?}");
-        }
-        path
-    });
+    compare_encode(
+        false,
+        box move |acc, n| {
+            let (mut path, optimal) = enc.encode(acc, n);
+            if let Some(path) = &mut path {
+                path.push(Inst::O);
+            }
+            if !optimal {
+                println!("{acc} -> {n} may not be optimal with {path:?}");
+            }
+            path
+        },
+    );
+}
+
+#[test]
+fn compare_golf() {
+    // Only `0 -> n` is in the table
+    compare_encode(true, box |_, n| Some(Inst::encode_number_golf(n)));
 }
 
-fn compare_encode(mut f: Box<dyn FnMut(Acc, Acc) -> Option<Vec<Inst>>>) {
+fn compare_encode(from_0_only: bool, mut f: Box<dyn FnMut(Acc, Acc) -> Option<Vec<Inst>>>) {
     fn compare(acc: Acc, n: Acc, path: Option<Vec<Inst>>, known_paths: &[Vec<Inst>]) {
         if let Some(path) = path {
             for p in known_paths {
-                assert_eq!(n, Inst::eval(p, acc), "{:?}", p);
+                assert!(Inst::verify_path(acc, n, p), "{:?}", p);
             }
             assert!(
                 known_paths.iter().find(|&p| &path == p).is_some(),
@@ -134,7 +164,9 @@ fn compare_encode(mut f: Box<dyn FnMut(Acc, Acc) -> Option<Vec<Inst>>>) {
     macro_rules! encode(($acc:literal -> $n:literal [$($insts:tt),+]) => {
         let acc = Acc::from($acc);
         let n = Acc::from($n);
-        compare(acc, n, f(acc, n), &[$(insts![$insts]),+]);
+        if acc == 0 || !from_0_only {
+            compare(acc, n, f(acc, n), &[$(insts![$insts]),+]);
+        }
     });
 
     // The encodings for 0 -> 1..=255 are consistent with the shortest solutions
@@ -404,6 +436,14 @@ fn compare_encode(mut f: Box<dyn FnMut(Acc, Acc) -> Option<Vec<Inst>>>) {
 
     encode!(0 -> 257 [iissisddddddddddddddddddddddddddddddddo]);
 
+    // Crossing 256 by squaring, without resetting to 0
+    encode!(12 -> 150 [siiiiiio]);
+    encode!(15 -> 230 [siiiiio]);
+    encode!(17 -> 300 [siiiiiiiiiiio]);
+    encode!(18 -> 300 [dsiiiiiiiiiiio]);
+    encode!(50 -> 2600 [isdo]);
+    encode!(100 -> 10000 [so]);
+
     // "Hello, World!"
     encode!(0 -> 72 [iiisdsiiiiiiiio]);
     encode!(72 -> 101 [ssssiiisisio]);
@@ -436,3 +476,1487 @@ fn slow_bfs() {
     enc.set_bound(8);
     assert_eq!((Some(path), false), enc.encode(acc, n));
 }
+
+#[test]
+fn nearest_sqrt_near_max() {
+    const FLOOR: u32 = 65535;
+    for n in FLOOR * FLOOR + 1..u32::MAX {
+        let (sqrt, offset) = Acc::from(n).nearest_sqrt();
+        assert_eq!(Acc::from(FLOOR), sqrt, "n={n}");
+        assert_eq!(Offset((n - FLOOR * FLOOR) as i64), offset, "n={n}");
+        assert_eq!(sqrt.square() + offset, n, "n={n}");
+    }
+}
+
+#[test]
+fn nearest_sqrt_not_256() {
+    for n in 65026..66048 {
+        let n = Acc::from(n);
+        let (sqrt, offset) = n.nearest_sqrt();
+        assert_ne!(256, sqrt.value(), "n={n}");
+        assert_eq!(sqrt.square() + offset, n, "n={n}");
+    }
+}
+
+#[test]
+fn bfs_encode_closest_square() {
+    // The closest square is the last node queued
+    let mut enc = BfsEncoder::with_bound(1);
+    assert_eq!(
+        (Some(insts![diiisdd]), false),
+        enc.encode(Acc::from(1), Acc::from(7))
+    );
+    let mut enc = BfsEncoder::with_bound(2);
+    assert_eq!(
+        (Some(insts![isiii]), false),
+        enc.encode(Acc::from(1), Acc::from(7))
+    );
+}
+
+#[test]
+fn bfs_encode_within() {
+    let mut enc = BfsEncoder::new();
+    assert_eq!(None, enc.encode_within(Acc::new(), Acc::from(255), 5));
+    assert_eq!(None, enc.encode_within(Acc::new(), Acc::from(16), 3));
+    assert_eq!(
+        Some(insts![iiss]),
+        enc.encode_within(Acc::new(), Acc::from(16), 4)
+    );
+    assert_eq!(
+        Some(insts![iiss]),
+        enc.encode_within(Acc::new(), Acc::from(16), 20)
+    );
+}
+
+#[test]
+fn display_ir() {
+    assert_eq!(">> >> ", Ir::Prompts(2).to_string());
+    assert_eq!(">> \n>> \n>> \n", Ir::Blanks(3).to_string());
+    assert_eq!("5", Ir::Number(5.into()).to_string());
+    assert_eq!("-2", Ir::Number((-2).into()).to_string());
+}
+
+#[cfg(feature = "rand")]
+#[test]
+fn random_programs() {
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    let mut rng = StdRng::seed_from_u64(0xdead_f154);
+    for len in [0, 1, 10, 1000] {
+        let program = random_program(&mut rng, len);
+        assert_eq!(len, program.len());
+        let _ = Inst::eval(&program, Acc::new());
+    }
+
+    let program = random_program_weighted(&mut rng, 100, [1, 0, 0, 1]);
+    assert!(program
+        .iter()
+        .all(|&inst| inst == Inst::I || inst == Inst::O));
+}
+
+#[cfg(feature = "rand")]
+#[test]
+fn minimize_idempotent_random() {
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    // Minimizing depends only on the output, which minimizing preserves
+    let mut rng = StdRng::seed_from_u64(0x0dd_f154);
+    for len in [0, 1, 10, 100, 1000] {
+        for _ in 0..10 {
+            let program = random_program(&mut rng, len);
+            let minimized = Inst::minimize(&program);
+            assert_eq!(minimized, Inst::minimize(&minimized), "{program:?}");
+        }
+    }
+}
+
+#[test]
+fn encode_numbers_offsets() {
+    let numbers = vec![Acc::from(100), Acc::from(104), Acc::from(102)];
+    let program = Inst::encode_numbers(&numbers);
+    assert_eq!(insts![iiisiso iiiio ddo], program);
+    assert_eq!((numbers, Acc::from(102)), Inst::eval_numbers(&program));
+}
+
+#[test]
+fn interpret_limited() {
+    let program = insts![iio_io];
+
+    let mut stdout = Vec::new();
+    assert!(!Inst::interpret_limited(&program, &mut stdout, 4).unwrap());
+    assert_eq!(">> >> >> 2\n>> \n", String::from_utf8(stdout).unwrap());
+
+    let mut stdout = Vec::new();
+    assert!(Inst::interpret_limited(&program, &mut stdout, 6).unwrap());
+    assert_eq!(
+        ">> >> >> 2\n>> \n>> >> 3\n",
+        String::from_utf8(stdout).unwrap()
+    );
+}
+
+#[test]
+fn output_multiset() {
+    let counts = Inst::output_multiset(&insts![iisioo iio]);
+    let expected: HashMap<_, _> = [(Acc::from(5), 2), (Acc::from(7), 1)].into_iter().collect();
+    assert_eq!(expected, counts);
+    assert!(Inst::output_multiset(&insts![iis]).is_empty());
+}
+
+#[test]
+fn eval_unnormalized() {
+    let program = insts![ssso];
+    assert_eq!(Acc::from(0), Inst::eval(&program, Acc::from(2)));
+    assert_eq!(256, Inst::eval_unnormalized(&program, 2));
+
+    assert_eq!(Acc::from(0), Inst::eval(&insts![d], Acc::new()));
+    assert_eq!(-1, Inst::eval_unnormalized(&insts![d], 0));
+    assert_eq!(Acc::from(9), Inst::eval(&insts![iiiso], Acc::new()));
+    assert_eq!(9, Inst::eval_unnormalized(&insts![iiiso], 0));
+}
+
+#[test]
+fn encode_width() {
+    let w16 = Width::new(16).unwrap();
+    let w32 = Width::default();
+    assert_eq!(None, Width::new(8));
+    assert_eq!(65535, w16.mask());
+
+    // 40000 has 6 trailing zeros, so it overflows to 0 after 2 squares with 16
+    // bits, but after 3 squares with 32 bits.
+    let acc = Acc::from(40000);
+    assert_eq!(Acc::new(), w16.eval(&insts![ss], acc));
+    assert_ne!(Acc::new(), w32.eval(&insts![ss], acc));
+    assert_eq!(Acc::new(), w32.eval(&insts![sss], acc));
+
+    let mut b16 = Builder::with_width(acc, w16);
+    b16.push_number(Acc::new());
+    assert_eq!(insts![sso], b16.insts());
+    let mut b32 = Builder::with_width(acc, w32);
+    b32.push_number(Acc::new());
+    assert_eq!(insts![ssso], b32.insts());
+
+    let mut enc = BfsEncoder::new();
+    enc.set_width(w16);
+    assert_eq!((Some(insts![ss]), true), enc.encode(acc, Acc::new()));
+
+    // The nearest root of 65534 is 256, which is not a valid accumulator and
+    // squares to 0 with 16 bits
+    let n = Acc::from(65534);
+    let mut b16 = Builder::with_width(Acc::new(), w16);
+    b16.push_number(n);
+    assert_eq!(n, w16.eval(b16.insts(), Acc::new()));
+    assert_eq!(
+        vec![n],
+        Inst::eval_numbers(&Inst::encode_number(Acc::new(), n)).0
+    );
+}
+
+#[test]
+fn max_output_run() {
+    assert_eq!((Acc::from(1), 3), Inst::max_output_run(&insts![iooo ioo]));
+    assert_eq!(
+        (Acc::from(108), 2),
+        Inst::max_output_run(&insts![
+            iisiiiisiiiiiiiioiiiiiiiiiiiiiiiiiiiiiiiiiiiiioiiiiiiiooiiio
+        ])
+    );
+    assert_eq!((Acc::new(), 0), Inst::max_output_run(&insts![iis]));
+}
+
+#[test]
+fn from_trajectory() {
+    let trajectory = [0, 1, 2, 4].map(Acc::from);
+    assert_eq!(Some(insts![iis]), Inst::from_trajectory(&trajectory));
+    let trajectory = [4, 16, 0, 0, 1].map(Acc::from);
+    assert_eq!(Some(insts![ssdi]), Inst::from_trajectory(&trajectory));
+    assert_eq!(None, Inst::from_trajectory(&[0, 3].map(Acc::from)));
+    assert_eq!(Some(vec![]), Inst::from_trajectory(&[Acc::new()]));
+}
+
+#[test]
+fn encode_range() {
+    let program = Inst::encode_range(1, 5).unwrap();
+    assert_eq!(insts![io io io io io], program);
+    let mut stdout = Vec::new();
+    Ir::interpret(&Ir::eval(&program).0, &mut stdout).unwrap();
+    assert_eq!(
+        ">> >> 1\n>> >> 2\n>> >> 3\n>> >> 4\n>> >> 5\n",
+        String::from_utf8(stdout).unwrap()
+    );
+
+    assert_eq!(Some(insts![iiio do do]), Inst::encode_range(3, 1));
+    let numbers = (257..=265).map(Acc::from).collect::<Vec<_>>();
+    let program = Inst::encode_range(257, 265).unwrap();
+    assert_eq!(numbers, Inst::eval_numbers(&program).0);
+    let numbers = (-10..=-2).rev().map(Acc::from).collect::<Vec<_>>();
+    let program = Inst::encode_range(-2, -10).unwrap();
+    assert_eq!(numbers, Inst::eval_numbers(&program).0);
+
+    // 256 and -1 reset to 0, so they can't be printed
+    assert_eq!(None, Inst::encode_range(250, 260));
+    assert_eq!(None, Inst::encode_range(256, 256));
+    assert_eq!(None, Inst::encode_range(3, -3));
+}
+
+#[test]
+fn heuristic_optimal() {
+    for acc in 0..=20 {
+        for n in 0..=20 {
+            let (acc, n) = (Acc::from(acc), Acc::from(n));
+            if let Err((heuristic, bfs)) = heuristic_is_optimal(acc, n, 12) {
+                panic!("{acc} -> {n}: heuristic {heuristic:?} longer than {bfs:?}");
+            }
+        }
+    }
+    // Known to be suboptimal
+    assert_eq!(
+        Err((insts![dsssiissddo], insts![dssiissddo])),
+        heuristic_is_optimal(Acc::from(129), Acc::from(14), 12)
+    );
+}
+
+#[test]
+fn ir_cursor() {
+    let program = insts![
+        iisiiiisiiiiiiiioiiiiiiiiiiiiiiiiiiiiiiiiiiiiioiiiiiiiooiiio_
+        dddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddo_
+        dddddddddddddddddddddsddoddddddddoiiioddddddoddddddddo_
+    ];
+    let (ir, _) = Ir::eval(&program);
+    let mut shell = Vec::new();
+    Inst::interpret(&program, &mut shell).unwrap();
+    let shell = String::from_utf8(shell).unwrap();
+
+    let render = |cursor: IrCursor<'_>| {
+        let mut s = String::new();
+        for event in cursor {
+            match event {
+                IrEvent::Number(n) => writeln!(s, "{n}").unwrap(),
+                IrEvent::Prompt => s.push_str(">> "),
+                IrEvent::Blank => s.push('\n'),
+                IrEvent::Reset => {}
+            }
+        }
+        s
+    };
+    assert_eq!(shell, render(IrCursor::new(&ir)));
+
+    let mut cursor = IrCursor::new(&ir);
+    let head = cursor.by_ref().take(20).count();
+    assert_eq!(20, head);
+    assert_eq!((2, 2), cursor.position());
+    let tail = render(IrCursor::with_position(&ir, cursor.position()));
+    assert_eq!(&shell[">> ".len() * 19 + "72\n".len()..], tail);
+}
+
+#[test]
+fn reachable_closure() {
+    let w9 = Width::new(9).unwrap();
+    let closure = w9.reachable_closure(Acc::new());
+    assert_eq!(510, closure.len());
+    assert!(closure.contains(&Acc::from(255)));
+    assert!(closure.contains(&Acc::from(289)));
+    assert!(!closure.contains(&Acc::from_raw(511)));
+
+    // Every value resets to 0, so the closure from any value is the same
+    let w12 = Width::new(12).unwrap();
+    let closure = w12.reachable_closure(Acc::from(3000));
+    assert_eq!(w12.reachable_closure(Acc::new()), closure);
+    assert_eq!((1 << 12) - 2, closure.len());
+    assert!(closure.contains(&Acc::new()));
+    assert!(closure.iter().all(|&acc| acc != 256 && acc != w12.mask()));
+}
+
+#[test]
+fn segments() {
+    let program = insts![iioiiioii];
+    assert_eq!(
+        vec![
+            (Acc::from(0), Acc::from(2), &program[0..3]),
+            (Acc::from(2), Acc::from(5), &program[3..7]),
+        ],
+        Inst::segments(&program)
+    );
+    assert_eq!(&insts![iiio][..], Inst::segments(&program)[1].2);
+    assert!(Inst::segments(&insts![iis]).is_empty());
+}
+
+#[test]
+fn encode_numbers_from() {
+    let numbers = [Acc::from(5), Acc::from(10)];
+    let program = Inst::encode_numbers_from(Acc::from(3), &numbers);
+    assert_eq!(insts![iioddsio], program);
+    let mut from_0 = insts![iii];
+    from_0.extend_from_slice(&program);
+    assert_eq!(numbers.to_vec(), Inst::eval_numbers(&from_0).0);
+}
+
+#[test]
+fn decimal_len() {
+    for n in [
+        0,
+        1,
+        9,
+        10,
+        99,
+        100,
+        255,
+        257,
+        1 << 20,
+        i32::MAX,
+        -2,
+        -10,
+        i32::MIN,
+    ] {
+        assert_eq!(n.to_string().len(), Acc::from(n).decimal_len(), "{n}");
+    }
+}
+
+#[test]
+fn output_bytes() {
+    // Outputs 288, a blank, and -2030932031
+    let program = insts![iissisdo_ ddddddddddddddddddddddddddddddddiisisssso];
+    let mut stdout = Vec::new();
+    Inst::interpret(&program, &mut stdout).unwrap();
+    assert_eq!(stdout, Inst::output_bytes(&program).collect::<Vec<_>>());
+    assert_eq!(stdout.len(), Inst::output_byte_len(&program));
+}
+
+#[test]
+fn free_output_count() {
+    let program = Inst::encode_numbers(&[5, 5, 6, 6, 6].map(Acc::from).to_vec());
+    assert_eq!(3, Inst::free_output_count(&program));
+    assert_eq!(2, Inst::free_output_count(&insts![ooio]));
+    assert_eq!(0, Inst::free_output_count(&insts![iodo]));
+}
+
+#[test]
+fn wrapping_sqrts_in_domain() {
+    let roots = Acc::new().wrapping_sqrts_in_domain();
+    assert!(roots.iter().all(|root| root.square() == 0));
+    assert!(roots.windows(2).all(|w| w[0] < w[1]));
+    for n in [0, 16, u32::MAX - 15, 65536, 1 << 31] {
+        assert!(roots.contains(&Acc::from(n)), "{n}");
+    }
+    assert_eq!(
+        [3, (1 << 31) - 3, (1 << 31) + 3, u32::MAX - 2].map(Acc::from)[..],
+        Acc::from(9).wrapping_sqrts_in_domain()[..]
+    );
+
+    // Check exactly against every value at a smaller width
+    let w16 = Width::new(16).unwrap();
+    for n in [0, 1, 9, 289, 4096] {
+        let n = Acc::from(n);
+        let expected = (0..=w16.mask())
+            .filter(|&x| w16.normalize(x) == x)
+            .map(Acc::from)
+            .filter(|&x| w16.apply(x, Inst::S) == n)
+            .collect::<Vec<_>>();
+        assert_eq!(expected, w16.wrapping_sqrts_in_domain(n), "{n:?}");
+    }
+}
+
+#[test]
+fn finish_with_eof_prompt() {
+    let mut b = Builder::new(Acc::new());
+    b.push_string("Hi");
+    let (mut ir, _) = Ir::eval(b.insts());
+    b.finish_with_eof_prompt();
+    Ir::finish_with_eof_prompt(&mut ir);
+    assert_eq!(Ir::eval(b.insts()).0, ir);
+
+    let mut stdout = Vec::new();
+    Inst::interpret(b.insts(), &mut stdout).unwrap();
+    let stdout = String::from_utf8(stdout).unwrap();
+    assert!(stdout.ends_with("105\n>> \n"), "{stdout:?}");
+    let mut ir_stdout = Vec::new();
+    Ir::interpret(&ir, &mut ir_stdout).unwrap();
+    assert_eq!(stdout.as_bytes(), ir_stdout);
+}
+
+#[test]
+fn retarget() {
+    let program = Inst::encode_numbers(&"Hello".chars().map(|c| Acc::from(c as u32)).collect());
+    let target = "Hallo"
+        .chars()
+        .map(|c| Acc::from(c as u32))
+        .collect::<Vec<_>>();
+    let segments = Inst::segments(&program);
+    let mut expected = segments[0].2.to_vec();
+    expected.extend(Inst::encode_number(Acc::from(72), Acc::from(97)));
+    expected.extend(Inst::encode_number(Acc::from(97), Acc::from(108)));
+    expected.extend(segments[3].2);
+    expected.extend(segments[4].2);
+    let retargeted = Inst::retarget(&program, &target);
+    assert_eq!(expected, retargeted);
+    assert_eq!(target, Inst::eval_numbers(&retargeted).0);
+    assert_eq!(
+        program,
+        Inst::retarget(&program, &Inst::eval_numbers(&program).0)
+    );
+}
+
+#[test]
+fn bfs_encode_canonical() {
+    let mut enc = BfsEncoder::with_bound(8);
+    // Also `iisiii`
+    assert_eq!(
+        Some(insts![iiisdd]),
+        enc.encode_canonical(Acc::new(), Acc::from(7))
+    );
+    enc.set_bound(10);
+    // Also `iisiiisdd`
+    assert_eq!(
+        Some(insts![iiisddsdd]),
+        enc.encode_canonical(Acc::new(), Acc::from(47))
+    );
+    assert_eq!(None, enc.encode_canonical(Acc::new(), Acc::from(200)));
+}
+
+#[test]
+fn step_toward() {
+    let target = Acc::from(16);
+    let mut acc = Acc::new();
+    let mut path = Vec::new();
+    while let Some(inst) = acc.step_toward(target) {
+        path.push(inst);
+        acc = acc.apply(inst);
+    }
+    assert_eq!(insts![iiss], path);
+    assert_eq!(None, Acc::new().step_toward(Acc::new()));
+    assert_eq!(Some(Inst::D), Acc::from(300).step_toward(Acc::from(290)));
+}
+
+#[test]
+fn inst_cursor_reset() {
+    use IrEvent::*;
+    let program = insts![iisssdo_];
+    let events = InstCursor::new(&program).collect::<Vec<_>>();
+    let expected = [
+        Prompt,
+        Prompt,
+        Prompt,
+        Prompt,
+        Prompt,
+        Reset,
+        Prompt,
+        Reset,
+        Prompt,
+        Number(Acc::new()),
+        Prompt,
+        Blank,
+    ];
+    assert_eq!(expected[..], events);
+
+    // Without resets, the events are the same as for the IR
+    let (ir, _) = Ir::eval(&program);
+    let visible = events.into_iter().filter(|&event| event != Reset);
+    assert!(visible.eq(IrCursor::new(&ir)));
+}
+
+#[test]
+fn borrowed_program() {
+    let v = insts![iissiiiiioiisiso_ddddo];
+    let insts = Insts::from_slice(&v);
+    assert_eq!(Inst::minimize(&v), Vec::from(insts.minimize()));
+    assert_eq!(Inst::eval_numbers(&v), insts.eval_numbers());
+    assert_eq!(Inst::eval(&v, Acc::from(3)), insts.eval(Acc::from(3)));
+    assert_eq!(v.len(), insts.len());
+
+    let program = insts.to_owned();
+    assert_eq!(insts, &*program);
+    assert_eq!(program, Program::parse("iissiiiiioiisiso_ddddo"));
+    assert_eq!(v, Vec::from(program));
+}
+
+#[test]
+fn encode_to_0_early_reset() {
+    // 2^30 + 4 has 2 trailing zeros, so would take 4 squares to have 32, but
+    // it squares to 16, then resets at 256 after only 2 squares
+    let n = Acc::from((1 << 30) + 4);
+    assert_eq!(Acc::new(), Inst::eval(&insts![ss], n));
+    assert_eq!(insts![sso], Inst::encode_number(n, Acc::new()));
+    assert_eq!(insts![ssiisio], Inst::encode_number(n, Acc::from(5)));
+}
+
+#[test]
+fn interpret_from() {
+    let mut stdout = Vec::new();
+    Inst::interpret_from(&insts![io], Acc::from(5), &mut stdout).unwrap();
+    assert_eq!(b">> >> 6\n", &*stdout);
+
+    let mut stdout = Vec::new();
+    Inst::interpret_from(&insts![sio], Acc::from(16), &mut stdout).unwrap();
+    assert_eq!(b">> >> >> 1\n", &*stdout);
+}
+
+#[test]
+fn encode_str_continuation() {
+    let mut program = Inst::encode_str_continuation(Acc::new(), "Hel");
+    let acc = Inst::eval(&program, Acc::new());
+    assert_eq!(Acc::from('l' as u32), acc);
+    let continuation = Inst::encode_str_continuation(acc, "lo");
+    assert_eq!(insts![oiiio], continuation);
+    program.extend(continuation);
+    assert_eq!("Hello", Inst::eval_string(&program).unwrap());
+}
+
+#[test]
+fn uses_reset() {
+    assert!(Inst::uses_reset(&insts![sso], Acc::from(16)));
+    assert!(!Inst::uses_reset(&insts![do], Acc::from(1)));
+    // Decrementing 0 resets from -1
+    assert!(Inst::uses_reset(&insts![ddo], Acc::from(1)));
+    // Outputs 0 without a reset
+    assert!(!Inst::uses_reset(&insts![oiido], Acc::new()));
+}
+
+#[test]
+fn push_numbers_with_progress() {
+    let numbers = [72, 101, 108, 108, 111].map(Acc::from);
+    let mut b = Builder::new(Acc::new());
+    let mut indices = Vec::new();
+    b.push_numbers_with_progress(numbers.into_iter(), |i| indices.push(i));
+    assert_eq!(vec![0, 1, 2, 3, 4], indices);
+    assert_eq!(Inst::encode_numbers(&numbers.to_vec()), b.into_insts());
+}
+
+#[test]
+fn encode_min_output_width() {
+    let program = Inst::encode_min_output_width(&[
+        &[Acc::from(100), Acc::from(7)],
+        &[Acc::from(-5), Acc::from(12)],
+        // Same width, so the closer to 12
+        &[Acc::from(90), Acc::from(15)],
+    ]);
+    assert_eq!(
+        vec![Acc::from(7), Acc::from(12), Acc::from(15)],
+        Inst::eval_numbers(&program).0
+    );
+}
+
+#[test]
+fn offset_checked() {
+    let offset = Offset::between(Acc::from(10), Acc::from(250));
+    assert_eq!(Some(Offset(240)), offset);
+    assert_eq!(
+        Some(Acc::from(250)),
+        offset.unwrap().checked_apply(Acc::from(10))
+    );
+    assert_eq!(
+        Some(Acc::from(255)),
+        offset.unwrap().checked_apply(Acc::from(15))
+    );
+    assert_eq!(None, Offset::between(Acc::from(10), Acc::from(300)));
+
+    // Crossing a reset
+    assert_eq!(None, offset.unwrap().checked_apply(Acc::from(16)));
+    assert_eq!(None, Offset(-1).checked_apply(Acc::new()));
+    assert_eq!(None, Offset(-1).checked_apply(Acc::from(257)));
+    assert_eq!(None, Offset(1).checked_apply(Acc::from(u32::MAX - 1)));
+    assert_eq!(
+        Some(Acc::from(257)),
+        Offset(-1).checked_apply(Acc::from(258))
+    );
+}
+
+#[test]
+fn encode_decimal_digits() {
+    let program = Inst::encode_decimal_digits(123);
+    assert_eq!(insts![ioioio], program);
+    assert_eq!([1, 2, 3].map(Acc::from)[..], Inst::eval_numbers(&program).0);
+    assert_eq!(insts![o], Inst::encode_decimal_digits(0));
+    let digits = [4, 2, 9, 4, 9, 6, 7, 2, 9, 5].map(Acc::from);
+    assert_eq!(
+        digits[..],
+        Inst::eval_numbers(&Inst::encode_decimal_digits(u32::MAX)).0
+    );
+}
+
+#[test]
+fn trim_trailing_noops() {
+    assert_eq!(&insts![iio][..], Inst::trim_trailing_noops(&insts![iioii]));
+    assert_eq!(
+        &insts![iio_][..],
+        Inst::trim_trailing_noops(&insts![iio_sd])
+    );
+    assert_eq!(&insts![o][..], Inst::trim_trailing_noops(&insts![o]));
+    assert!(Inst::trim_trailing_noops(&insts![iss]).is_empty());
+}
+
+#[test]
+fn value_widths() {
+    let v16 = Value16::from(300).square();
+    let v32 = Value32::from(300).square();
+    assert_eq!(90000 % (1 << 16), v16.value());
+    assert_eq!(90000, v32.value());
+    assert_eq!(
+        Width::new(16).unwrap().apply(Acc::from(300), Inst::S),
+        v16.to_acc()
+    );
+
+    // Resets at 256 and -1 at the width
+    assert_eq!(0, Value16::from(16).square().value());
+    assert_eq!(0, Value16::new().decrement().value());
+    assert_eq!(0, Value16::from(u16::MAX as u32).value());
+    assert_eq!(u16::MAX as u32, Value32::from(u16::MAX as u32).value());
+    assert_eq!(0, Value::<9>::from(511).value());
+    assert_eq!(484, Value::<9>::from(22).square().value());
+
+    // 40000 overflows to 0 after 2 squares with 16 bits, but 3 with 32 bits
+    assert_eq!(
+        insts![sso],
+        Value16::from(40000).encode_number(Value16::new())
+    );
+    assert_eq!(
+        insts![ssso],
+        Value32::from(40000).encode_number(Value32::new())
+    );
+}
+
+#[test]
+fn encode_accumulator() {
+    let mut enc = BfsEncoder::with_bound(12);
+    for (acc, n) in [(0, 16), (12, 150), (100, 10000), (300, 2)] {
+        assert_eq!(
+            enc.encode_accumulator(Acc::from(acc), Acc::from(n)),
+            enc.encode_accumulator(Value32::from(acc), Value32::from(n)),
+            "{acc} -> {n}",
+        );
+    }
+    assert_eq!(
+        (Some(insts![ss]), true),
+        enc.encode_accumulator(Value16::from(40000), Value16::new())
+    );
+    assert_eq!(
+        (Some(insts![sss]), true),
+        enc.encode_accumulator(Acc::from(40000), Acc::new())
+    );
+
+    let mut b = Builder::from_accumulator(Value16::from(40000));
+    b.push_number(Acc::new());
+    assert_eq!(insts![sso], b.insts());
+}
+
+#[test]
+fn annotate() {
+    let one = Acc::from(1);
+    assert_eq!(
+        vec![(Inst::I, one), (Inst::S, one), (Inst::O, one)],
+        Inst::annotate(&insts![iso])
+    );
+    let annotated = Inst::annotate(&insts![iiss_o]);
+    let accs = annotated
+        .iter()
+        .map(|&(_, acc)| acc.value())
+        .collect::<Vec<_>>();
+    assert_eq!(vec![1, 2, 4, 16, 16, 16], accs);
+}
+
+#[test]
+fn encode_char() {
+    assert_eq!(insts![iiisdsiiiiiiiio], Inst::encode_char(Acc::new(), 'H'));
+    assert_eq!(insts![iiiiio], Inst::encode_char(Acc::from(67), 'H'));
+    let decomposed = Inst::encode_char(Acc::new(), 'Ā');
+    assert_eq!("A\u{0304}", Inst::eval_string(&decomposed).unwrap());
+}
+
+#[test]
+fn shared_square_prefix() {
+    // 10^2 = 100 and 11^2 = 121, where 3^2 + 1 = 10 and 3^2 + 2 = 11
+    assert_eq!(
+        Some(Acc::from(3)),
+        Acc::from(100).shared_square_prefix(Acc::from(121))
+    );
+    assert_eq!(
+        Some(Acc::from(10)),
+        Acc::from(100).shared_square_prefix(Acc::from(10))
+    );
+    assert_eq!(
+        Some(Acc::from(10)),
+        Acc::from(10000).shared_square_prefix(Acc::from(99))
+    );
+    assert_eq!(None, Acc::from(5).shared_square_prefix(Acc::from(10)));
+}
+
+#[test]
+fn to_grid() {
+    let grid = Inst::to_grid(&insts![iissso], 4);
+    assert_eq!(vec![vec!['i', 'i', 's', 's'], vec!['s', 'o']], grid);
+    let grid = Inst::to_grid(&insts![io_do], 2);
+    assert_eq!(vec![vec!['i', 'o'], vec![' ', 'd'], vec!['o']], grid);
+}
+
+#[test]
+fn heuristic_distance() {
+    assert_eq!(4, Acc::new().heuristic_distance(Acc::from(16)));
+    assert_eq!(0, Acc::from(16).heuristic_distance(Acc::from(16)));
+    assert_eq!(2, Acc::from(18).heuristic_distance(Acc::from(16)));
+    // Not symmetric, since 16 squares to 256 and resets
+    assert_eq!(1, Acc::from(16).heuristic_distance(Acc::new()));
+    for n in [0, 7, 72, 255, 300, 1000] {
+        let n = Acc::from(n);
+        let len = Inst::encode_number(Acc::from(5), n).len() - 1;
+        assert_eq!(len as u32, Acc::from(5).heuristic_distance(n));
+    }
+}
+
+#[test]
+fn encode_stream() {
+    let mut out = Vec::new();
+    Inst::encode_stream([72, 105].into_iter(), &mut out).unwrap();
+    let program = Inst::parse(&out);
+    assert_eq!("Hi", Inst::eval_string(&program).unwrap());
+    assert_eq!(
+        Inst::encode_numbers(&vec![Acc::from(72), Acc::from(105)]),
+        program
+    );
+}
+
+#[test]
+fn concat_optimal() {
+    let hell = Inst::encode_str_continuation(Acc::new(), "Hell");
+    let hello = Inst::concat_optimal(&hell, &[Acc::from('o' as u32)]);
+    assert_eq!(hell[..], hello[..hell.len()]);
+    assert_eq!(insts![iiio][..], hello[hell.len()..]);
+    assert_eq!("Hello", Inst::eval_string(&hello).unwrap());
+}
+
+#[test]
+fn empty_program() {
+    let empty: &[Inst] = &[];
+    assert_eq!(Acc::from(5), Inst::eval(empty, Acc::from(5)));
+    assert_eq!((vec![], Acc::new()), Inst::eval_numbers(empty));
+    assert_eq!(Some(String::new()), Inst::eval_string(empty));
+    assert_eq!((vec![], Acc::new()), Ir::eval(empty));
+    assert_eq!(Vec::<Inst>::new(), Inst::minimize(empty));
+    assert_eq!(Vec::<Inst>::new(), Inst::encode_numbers(&vec![]));
+    assert_eq!(Vec::<Inst>::new(), Inst::retarget(empty, &[]));
+    assert!(Inst::segments(empty).is_empty());
+    assert!(Inst::annotate(empty).is_empty());
+    assert!(Inst::trim_trailing_noops(empty).is_empty());
+    assert!(Inst::to_grid(empty, 4).is_empty());
+    assert_eq!(0, Inst::free_output_count(empty));
+    assert_eq!((Acc::new(), 0), Inst::max_output_run(empty));
+    assert!(!Inst::uses_reset(empty, Acc::new()));
+    assert!(Insts::from_slice(empty).minimize().is_empty());
+
+    let mut stdout = Vec::new();
+    Inst::interpret(empty, &mut stdout).unwrap();
+    Ir::interpret(&[], &mut stdout).unwrap();
+    assert!(Inst::interpret_limited(empty, &mut stdout, 0).unwrap());
+    assert!(stdout.is_empty());
+    assert_eq!(0, Inst::output_byte_len(empty));
+    assert_eq!(0, Inst::output_bytes(empty).count());
+    assert_eq!(0, InstCursor::new(empty).count());
+    assert_eq!(0, IrCursor::new(&[]).count());
+
+    let mut b = Builder::new(Acc::from(3));
+    b.push_numbers([].into_iter());
+    b.append(empty);
+    assert!(b.insts().is_empty());
+    assert_eq!(Acc::from(3), b.acc());
+}
+
+#[test]
+fn builder_limit() {
+    let mut b = Builder::with_limit(Acc::new(), 20);
+    b.try_push_number(Acc::from(72)).unwrap();
+    let insts = b.insts().to_vec();
+    let err = LimitExceeded {
+        len: insts.len() + 1_000_000,
+        limit: 20,
+    };
+    assert_eq!(Err(err), b.try_add(1_000_000));
+    assert_eq!(Err(err), b.try_sub(1_000_000));
+    // 72 -> 1000 needs more than the remaining 5 instructions
+    assert!(b.try_push_number(Acc::from(1000)).is_err());
+    assert_eq!(insts, b.insts());
+    assert_eq!(Acc::from(72), b.acc());
+
+    b.try_push_number(Acc::from(73)).unwrap();
+    assert_eq!(Acc::from(73), b.acc());
+    assert!(b.insts().len() <= b.limit());
+}
+
+#[test]
+fn interactive_session() {
+    let mut stdout = Vec::new();
+    Inst::interactive_session(&b"iso\n"[..], &mut stdout).unwrap();
+    assert_eq!(
+        ">> iso\n>> >> 1\n>> \n>> ",
+        String::from_utf8(stdout).unwrap()
+    );
+
+    let mut stdout = Vec::new();
+    Inst::interactive_session(&b"iio\nso"[..], &mut stdout).unwrap();
+    assert_eq!(
+        ">> iio\n>> >> 2\n>> \n>> so>> 4\n>> ",
+        String::from_utf8(stdout).unwrap()
+    );
+}
+
+#[test]
+fn min_squares_from_zero() {
+    assert_eq!(0, Acc::from(16).min_squares_from_zero());
+    assert_eq!(0, Acc::from(255).min_squares_from_zero());
+    assert_eq!(1, Acc::from(257).min_squares_from_zero());
+    assert_eq!(1, Acc::from(-2).min_squares_from_zero());
+    // Reachable without squares, but shorter with them
+    let program = Inst::encode_number(Acc::new(), Acc::from(16));
+    assert_eq!(2, program.iter().filter(|&&inst| inst == Inst::S).count());
+}
+
+#[test]
+fn square_orbit() {
+    // 16 squares to 256, which resets to 0
+    let orbit = [2, 4, 16, 0, 0].map(Acc::from);
+    assert_eq!(orbit[..], Acc::from(2).square_orbit(4));
+    assert_eq!(vec![Acc::from(3)], Acc::from(3).square_orbit(0));
+    for count in 0..6 {
+        let orbit = Acc::from(3).square_orbit(count);
+        assert_eq!(Acc::from(3).square_repeat(count), *orbit.last().unwrap());
+    }
+}
+
+#[test]
+fn push_number_from_above() {
+    // 16^2 resets, so it decrements from 255
+    let mut b = Builder::new(Acc::new());
+    b.push_number_from_above(Acc::from(250));
+    let mut expected = Inst::encode_number(Acc::new(), Acc::from(255));
+    expected.pop();
+    expected.extend(insts![dddddo]);
+    assert_eq!(expected, b.insts());
+    assert_eq!(vec![Acc::from(250)], Inst::eval_numbers(b.insts()).0);
+
+    let mut b = Builder::new(Acc::new());
+    b.push_number_from_above(Acc::from(280));
+    assert_eq!(insts![iissisdddddddddo], b.insts());
+    b.push_number_from_above(Acc::from(100));
+    assert_eq!(
+        [280, 100].map(Acc::from)[..],
+        Inst::eval_numbers(b.insts()).0
+    );
+}
+
+#[test]
+fn validate_strict() {
+    assert_eq!(Ok(()), Inst::validate_strict(&insts![iisso]));
+    assert_eq!(Ok(()), Inst::validate_strict(&[]));
+    assert_eq!(Err(3), Inst::validate_strict(&Inst::parse("iis so\n")));
+}
+
+#[test]
+fn suggest_insertion() {
+    // 15 is `iissdo`, but 16 is `iisso`
+    let program = insts![iiiiiiiiiiiiiiio];
+    assert_eq!(Some((0, Inst::I)), Inst::suggest_insertion(&program));
+    assert_eq!(None, Inst::suggest_insertion(&insts![o]));
+}
+
+#[test]
+fn describe() {
+    assert_eq!("0 (or reset from 256/-1)", Acc::new().describe());
+    assert_eq!("42", Acc::from(42).describe());
+    assert_eq!("-2", Acc::from(-2).describe());
+}
+
+#[test]
+fn minimize_batch() {
+    let encode = |s: &str| Inst::encode_numbers(&s.chars().map(|c| Acc::from(c as u32)).collect());
+    let programs = vec![
+        insts![iiiiiiiiiiiiiiiiiiiiiiiiiiiiiiiiiiiiiiiiiiiiiiiiiiiiiiiiiiiiiiiiiiiiiiiio],
+        encode("Hello, World!"),
+        encode("Hello, Deadfish!"),
+        vec![],
+    ];
+    let minimized = programs
+        .iter()
+        .map(|p| Inst::minimize(p))
+        .collect::<Vec<_>>();
+    assert_eq!(minimized, Inst::minimize_batch(&programs));
+
+    let mut enc = CachingEncoder::new();
+    let _ = enc.encode_numbers(&Inst::eval_numbers(&programs[1]).0);
+    let len = enc.len();
+    let _ = enc.encode_numbers(&Inst::eval_numbers(&programs[2]).0);
+    // Only transitions after "Hello, " are new
+    assert_eq!(len + "Deadfish!".len(), enc.len());
+}
+
+#[test]
+fn output_at() {
+    let program = insts![ioiioiiio];
+    assert_eq!(Some(Acc::from(1)), Inst::output_at(&program, 0));
+    assert_eq!(Some(Acc::from(3)), Inst::output_at(&program, 1));
+    assert_eq!(Some(Acc::from(6)), Inst::output_at(&program, 2));
+    assert_eq!(None, Inst::output_at(&program, 3));
+}
+
+#[test]
+fn parse_with_alphabet() {
+    let program = insts![iisso_ddo];
+    let symbolic = Inst::parse_with_alphabet("++**. --.", &InstAlphabet::symbolic());
+    assert_eq!(program, symbolic);
+    let upper = Inst::parse_with_alphabet("IIsSo DDO", &InstAlphabet::case_insensitive());
+    assert_eq!(program, upper);
+    let src = "iisso+ddo";
+    assert_eq!(
+        Inst::parse(src),
+        Inst::parse_with_alphabet(src, &InstAlphabet::default())
+    );
+}
+
+#[test]
+fn encode_str_min_squares() {
+    let squares = |insts: &[Inst]| insts.iter().filter(|&&inst| inst == Inst::S).count();
+    for s in ["Hello, World!", "Ātra", "ǅ¡\u{1F41F}"] {
+        let program = Inst::encode_str_min_squares(s);
+        let mut b = Builder::new(Acc::new());
+        b.push_string(s);
+        assert!(squares(&program) <= squares(b.insts()), "{s}");
+        assert_eq!(Inst::eval_string(b.insts()), Inst::eval_string(&program));
+    }
+    // 'H' (72) is reached by offset alone
+    assert_eq!(0, squares(&Inst::encode_str_min_squares("Hi")));
+    // U+1F41F 🐟 needs one square
+    assert_eq!(1, squares(&Inst::encode_str_min_squares("\u{1F41F}")));
+}
+
+#[test]
+fn encode_number_fast() {
+    for acc in [0, 1, 16, 255, 300, 65536, u32::MAX - 1] {
+        let acc = Acc::from(acc);
+        for n in 0..256 {
+            let n = Acc::from(n);
+            let fast = Inst::encode_number_fast(acc, n);
+            assert_eq!(Some(&Inst::O), fast.last());
+            assert_eq!(n, Inst::eval(&fast, acc), "{acc} -> {n}");
+            assert!(fast.len() >= Inst::encode_number(acc, n).len());
+        }
+    }
+    for n in [256, 257, 1000, 65535, 1 << 20, u32::MAX - 2] {
+        let n = Acc::from(n);
+        assert_eq!(
+            n,
+            Inst::eval(&Inst::encode_number_fast(Acc::new(), n), Acc::new())
+        );
+    }
+}
+
+#[test]
+fn common_outputs() {
+    let a = insts![i o i o i o];
+    let b = insts![i i o i o i o];
+    assert_eq!(
+        vec![Acc::from(2), Acc::from(3)],
+        Inst::common_outputs(&a, &b)
+    );
+    assert_eq!(
+        vec![Acc::new()],
+        Inst::common_outputs(&insts![o o], &insts![o i o d o])
+    );
+    assert!(Inst::common_outputs(&a, &insts![s]).is_empty());
+}
+
+#[test]
+fn encode_to_0_roots_of_256() {
+    // Every value that reaches 256 by squaring resets to 0 in at most that
+    // many squares. 256 has 8 trailing zeros, so its roots nest 3 deep.
+    let mut roots = Acc::new()
+        .wrapping_sqrts_in_domain()
+        .into_iter()
+        .filter(|&root| root.value().wrapping_mul(root.value()) == 256)
+        .collect::<Vec<_>>();
+    for (squares, count) in [(1, 64), (2, 512), (3, 2048)] {
+        assert_eq!(count, roots.len());
+        for &root in &roots {
+            let program = Inst::encode_number(root, Acc::new());
+            assert_eq!(Acc::new(), Inst::eval(&program, root), "{root:?}");
+            assert!(program.len() <= squares + 1, "{root:?} {program:?}");
+        }
+        roots = roots
+            .iter()
+            .flat_map(|&root| root.wrapping_sqrts_in_domain())
+            .collect();
+    }
+    assert!(roots.is_empty());
+}
+
+#[test]
+fn display_alternate_unsigned() {
+    let acc = Acc::from(u32::MAX - 2);
+    assert_eq!("-3", format!("{acc}"));
+    assert_eq!("4294967293", format!("{acc:#}"));
+    assert_eq!("42", format!("{:#}", Acc::from(42)));
+}
+
+#[test]
+fn builder_goto() {
+    let mut b = Builder::new(Acc::new());
+    b.goto(Acc::from(16));
+    assert_eq!(insts![iiss], b.insts());
+    assert_eq!(Acc::from(16), b.acc());
+    b.push_number(Acc::from(17));
+    assert_eq!(insts![iissio], b.insts());
+}
+
+#[test]
+fn encode_numbers_bounded_gap() {
+    // 100 (10^2) takes at least 6 instructions from 16
+    let numbers = [Acc::from(16), Acc::from(100), Acc::from(99)];
+    assert_eq!(None, Inst::encode_numbers_bounded_gap(&numbers, 5));
+    let program = Inst::encode_numbers_bounded_gap(&numbers, 8).unwrap();
+    assert_eq!(
+        (numbers.to_vec(), Acc::from(99)),
+        Inst::eval_numbers(&program)
+    );
+    let max_gap = program.split(|&inst| inst == Inst::O).map(<[_]>::len).max();
+    assert!(max_gap <= Some(8));
+}
+
+#[test]
+fn apply_raw() {
+    assert_eq!(256, Acc::from(16).apply_raw(Inst::S));
+    assert_eq!(Acc::new(), Acc::from(16).apply(Inst::S));
+    assert_eq!(u32::MAX, Acc::new().apply_raw(Inst::D));
+    assert_eq!(256, Acc::from(255).apply_raw(Inst::I));
+    assert_eq!(7, Acc::from(7).apply_raw(Inst::O));
+}
+
+#[test]
+fn builder_reach() {
+    let mut b = Builder::new(Acc::new());
+    b.reach(81);
+    assert_eq!(insts![iiiss], b.insts());
+    assert_eq!(Acc::from(81), b.acc());
+    b.reach(2);
+    assert_eq!(insts![iiissii], b.insts());
+    assert_eq!(Acc::from(83), b.acc());
+}
+
+#[test]
+fn edit_distance() {
+    assert_eq!(1, Inst::edit_distance(&insts![iiso], &insts![iso]));
+    assert_eq!(0, Inst::edit_distance(&insts![iiso], &insts![iiso]));
+    assert_eq!(4, Inst::edit_distance(&insts![], &insts![iiso]));
+    assert_eq!(2, Inst::edit_distance(&insts![iiso], &insts![dis]));
+}
+
+#[test]
+fn encode_generic() {
+    let program = Inst::encode(vec![72u8, 105]);
+    assert_eq!(Some("Hi".to_owned()), Inst::eval_string(&program));
+    assert_eq!(program, Inst::encode([72i16, 105]));
+    assert_eq!(
+        Inst::encode_numbers(&vec![Acc::from(-2)]),
+        Inst::encode([-2i32])
+    );
+}
+
+#[test]
+fn square_repeat_fixed_point() {
+    assert_eq!(Acc::from(1), Acc::from(1).square_repeat(1_000_000));
+    assert_eq!(Acc::from(1), Acc::from(3).square_repeat(u32::MAX));
+    assert_eq!(Acc::new(), Acc::from(6).square_repeat(u32::MAX));
+    assert_eq!(Acc::from(9), Acc::from(3).square_repeat(1));
+    let w16 = Width::new(16).unwrap();
+    assert_eq!(Acc::from(1), w16.square_repeat(Acc::from(3), u32::MAX));
+}
+
+#[test]
+fn verify_path() {
+    let acc = Acc::from(3);
+    assert!(Inst::verify_path(acc, Acc::from(10), &insts![sio]));
+    assert!(Inst::verify_path(Acc::new(), Acc::new(), &insts![o]));
+    assert!(!Inst::verify_path(acc, Acc::from(10), &insts![si]));
+    assert!(!Inst::verify_path(acc, Acc::from(11), &insts![sio]));
+    assert!(!Inst::verify_path(acc, acc, &insts![]));
+}
+
+#[test]
+fn shortest_for_predicate() {
+    let palindrome = |output: &[Acc]| output.iter().eq(output.iter().rev());
+    assert_eq!(
+        Some(insts![oo]),
+        crate::shortest_for_predicate(4, |output| output.len() == 2 && palindrome(output))
+    );
+
+    // A palindrome of distinct numbers must change and return
+    let distinct =
+        |output: &[Acc]| output.len() == 3 && output[0] != output[1] && palindrome(output);
+    let program = crate::shortest_for_predicate(6, distinct).unwrap();
+    assert_eq!(5, program.len());
+    assert!(distinct(&Inst::eval_numbers(&program).0));
+    assert_eq!(None, crate::shortest_for_predicate(4, distinct));
+}
+
+#[test]
+fn ir_from_numbers() {
+    for numbers in [vec![72, 101], vec![0, 0, 300, 5, 65536, u32::MAX - 2]] {
+        let numbers = numbers.into_iter().map(Acc::from).collect::<Vec<_>>();
+        let (ir, _) = Ir::eval(&Inst::encode_numbers(&numbers));
+        assert_eq!(ir, Ir::from_numbers(&numbers));
+    }
+}
+
+#[test]
+fn output_equivalent() {
+    use EquivalenceMode::*;
+    let a = insts![iio_io];
+    let b = insts![iioio_];
+    assert!(Inst::output_equivalent(&a, &b, NumbersOnly));
+    assert!(!Inst::output_equivalent(&a, &b, FullTranscript));
+    // Same numbers and prompts, but a different blank
+    let c = insts![i_ioio];
+    assert!(!Inst::output_equivalent(
+        &insts![_iioio],
+        &c,
+        FullTranscript
+    ));
+    assert!(Inst::output_equivalent(&a, &a, FullTranscript));
+    assert!(!Inst::output_equivalent(&a, &insts![io], NumbersOnly));
+}
+
+#[test]
+fn min_len_for_bytes() {
+    let mut enc = BfsEncoder::new();
+    let mut program = enc.encode(Acc::new(), Acc::from('H' as u32)).0.unwrap();
+    program.push(Inst::O);
+    program.extend(
+        enc.encode(Acc::from('H' as u32), Acc::from('i' as u32))
+            .0
+            .unwrap(),
+    );
+    program.push(Inst::O);
+    assert_eq!(Some("Hi".to_owned()), Inst::eval_string(&program));
+    assert_eq!(program.len(), crate::min_len_for_bytes(b"Hi"));
+
+    assert_eq!(0, crate::min_len_for_bytes(b""));
+    let numbers = vec![Acc::from(72), Acc::from(72), Acc::from(105)];
+    assert!(crate::min_len_for_bytes(b"HHi") <= Inst::encode_numbers(&numbers).len());
+}
+
+#[test]
+fn first_divergence() {
+    let program = insts![ioioiso];
+    assert_eq!(
+        (vec![Acc::from(1), Acc::from(2), Acc::from(9)], Acc::from(9)),
+        Inst::eval_numbers(&program)
+    );
+    let expected = [Acc::from(1), Acc::from(2), Acc::from(3)];
+    assert_eq!(Some(2), Inst::first_divergence(&program, &expected));
+    assert_eq!(None, Inst::first_divergence(&program, &expected[..2]));
+    assert_eq!(Some(0), Inst::first_divergence(&insts![o], &expected));
+    assert_eq!(None, Inst::first_divergence(&insts![io], &expected));
+}
+
+#[test]
+fn number_stream() {
+    let mut stream = NumberStream::new();
+    stream.encode_all(&[Acc::from(72)]);
+    assert_eq!(Acc::from(72), stream.acc());
+    stream.encode_all(&[Acc::from(101)]);
+    stream.encode(Acc::from(108));
+    let numbers = vec![Acc::from(72), Acc::from(101), Acc::from(108)];
+    assert_eq!(Inst::encode_numbers(&numbers), stream.finish());
+    assert!(NumberStream::default().finish().is_empty());
+}
+
+#[test]
+fn acc_range() {
+    let mut program = insts![iiiiiiiiiis];
+    program.extend([Inst::D; 100]);
+    assert_eq!(Acc::new(), Inst::eval(&program, Acc::new()));
+    assert_eq!((Acc::new(), Acc::from(100)), Inst::acc_range(&program));
+    assert_eq!((Acc::new(), Acc::new()), Inst::acc_range(&insts![]));
+    // Compared as signed, so negative numbers are less than 0
+    let program = Inst::encode_number(Acc::new(), Acc::from(-2));
+    let (min, _) = Inst::acc_range(&program);
+    assert!(min.value() as i32 <= -2, "{min}");
+}
+
+#[test]
+fn encode_repeat() {
+    let program = Inst::encode_repeat(Acc::from(5), 3);
+    assert_eq!(insts![iisiooo], program);
+    assert_eq!(
+        (vec![Acc::from(5); 3], Acc::from(5)),
+        Inst::eval_numbers(&program)
+    );
+    assert_eq!(insts![o], Inst::encode_repeat(Acc::new(), 1));
+    assert!(Inst::encode_repeat(Acc::from(5), 0).is_empty());
+}
+
+#[test]
+fn bfs_optimal_first_step() {
+    let mut enc = BfsEncoder::with_bound(8);
+    let n = Acc::from(16);
+    let mut acc = Acc::new();
+    let mut path = Vec::new();
+    while let Some(inst) = enc.optimal_first_step(acc, n) {
+        path.push(inst);
+        acc = acc.apply(inst);
+    }
+    assert_eq!(n, acc);
+    assert_eq!(insts![iiss], path);
+    assert_eq!(None, enc.optimal_first_step(n, n));
+    enc.set_bound(2);
+    assert_eq!(None, enc.optimal_first_step(Acc::new(), n));
+}
+
+#[test]
+fn has_output() {
+    assert!(!Inst::has_output(&insts![iisss]));
+    assert!(!Inst::has_output(&insts![i_d_]));
+    assert!(Inst::has_output(&insts![iso]));
+    assert_eq!(0, Inst::output_count(&insts![iisss]));
+    assert_eq!(3, Inst::output_count(&insts![oiso_o]));
+}
+
+#[test]
+fn heuristic_config_bias() {
+    let encode = |bias, acc, n| {
+        let config = HeuristicConfig { prefer_offset_bias: bias };
+        let mut b = Builder::with_heuristic_config(Acc::from(acc), config);
+        b.push_number(Acc::from(n));
+        b.into_insts()
+    };
+    // Borderline: the offset and squares are the same length from 4 to 1
+    assert_eq!(insts![dddo], encode(0, 4, 1));
+    assert_eq!(insts![ssio], encode(-1, 4, 1));
+    // Borderline: the offset is one longer from 0 to 4
+    assert_eq!(insts![iiso], encode(0, 0, 4));
+    assert_eq!(insts![iiiio], encode(1, 0, 4));
+    // Clear-cut
+    for bias in [-1, 0, 1] {
+        assert_eq!(insts![iisso], encode(bias, 0, 16));
+        assert_eq!(insts![io], encode(bias, 100, 101));
+    }
+    assert_eq!(
+        Inst::encode_number(Acc::new(), Acc::from(72)),
+        encode(0, 0, 72)
+    );
+}
+
+#[test]
+fn acc_all() {
+    assert_eq!(
+        [0, 1, 2, 3, 4].map(Acc::from)[..],
+        Acc::all().take(5).collect::<Vec<_>>()
+    );
+    let around_256 = Acc::all().skip(254).take(4).collect::<Vec<_>>();
+    assert_eq!([254, 255, 257, 258].map(Acc::from)[..], around_256);
+}
+
+#[test]
+fn interpret_verbose() {
+    let mut stdout = Vec::new();
+    Inst::interpret_verbose(&insts![iio], &mut stdout).unwrap();
+    assert_eq!(
+        ">> >> >> 2\n# 0 -> 2 by ii (2 increments)\n",
+        String::from_utf8(stdout).unwrap()
+    );
+
+    let mut stdout = Vec::new();
+    Inst::interpret_verbose(&insts![iiso_odsio], &mut stdout).unwrap();
+    assert_eq!(
+        ">> >> >> >> 4\n# 0 -> 4 by iis (2 increments, 1 square)\n\
+         >> \n>> 4\n# 4 again\n\
+         >> >> >> >> 10\n# 4 -> 10 by dsi (1 increment, 1 decrement, 1 square)\n",
+        String::from_utf8(stdout).unwrap()
+    );
+}
+
+#[test]
+fn route() {
+    use RouteStep::*;
+    let route = Route::from(vec![Offset(8.into()), Square, Offset(8.into())]);
+    assert_eq!(Acc::from(72), route.apply(Acc::new()));
+    assert_eq!(insts![iiiiiiiisiiiiiiii], route.to_insts());
+
+    let mut composed = Route::new();
+    composed.push(Offset((-8).into()));
+    composed.extend(&route);
+    assert_eq!(Acc::from(72), composed.apply(Acc::from(8)));
+    assert_eq!(
+        Acc::from(72),
+        Inst::eval(&composed.to_insts(), Acc::from(8))
+    );
+    // Squaring 16 resets
+    assert_eq!(Acc::new(), Route::from(vec![Square]).apply(Acc::from(16)));
+}
+
+#[test]
+fn encode_min_instruction_kinds() {
+    let kinds = |insts: &[Inst]| {
+        let mut kinds = insts.to_vec();
+        kinds.sort();
+        kinds.dedup();
+        kinds
+    };
+    assert_eq!(insts![o], Inst::encode_min_instruction_kinds(Acc::new()));
+    assert_eq!(
+        insts![iiio],
+        Inst::encode_min_instruction_kinds(Acc::from(3))
+    );
+    assert_eq!(
+        insts![io],
+        kinds(&Inst::encode_min_instruction_kinds(Acc::from(255)))
+    );
+    for n in (257..300).chain([65535, 65536, 72900, 1 << 31, u32::MAX - 1]) {
+        let n = Acc::from(n);
+        let program = Inst::encode_min_instruction_kinds(n);
+        assert_eq!(insts![iso], kinds(&program), "{n}");
+        assert_eq!((vec![n], n), Inst::eval_numbers(&program));
+    }
+}
+
+#[test]
+fn estimated_cycles() {
+    let costs = [1, 1, 10, 5, 0];
+    assert_eq!(17, Inst::estimated_cycles(&insts![iiso], costs));
+    assert_eq!(2, Inst::estimated_cycles(&insts![d_d__], costs));
+    assert_eq!(0, Inst::estimated_cycles(&insts![], costs));
+}
+
+#[test]
+fn encode_number_no_reset() {
+    let values = [
+        0,
+        1,
+        15,
+        16,
+        255,
+        257,
+        300,
+        65000,
+        65535,
+        70000,
+        1 << 31,
+        u32::MAX - 1,
+    ];
+    for acc in values.map(Acc::from) {
+        for n in values.map(Acc::from) {
+            let program = Inst::encode_number_no_reset(acc, n);
+            assert!(!Inst::uses_reset(&program, acc), "{acc} -> {n} {program:?}");
+            assert_eq!(n, Inst::eval(&program, acc), "{acc} -> {n}");
+            assert_eq!(Some(&Inst::O), program.last());
+        }
+    }
+    // The heuristic increments 255 to 256, so it decrements instead
+    let acc = Acc::from(255);
+    assert_eq!(insts![iiisio], Inst::encode_number(acc, Acc::from(5)));
+    let mut expected = vec![Inst::D; 250];
+    expected.push(Inst::O);
+    assert_eq!(expected, Inst::encode_number_no_reset(acc, Acc::from(5)));
+}
+
+#[test]
+fn output_entropy() {
+    assert!(Inst::output_entropy(&insts![iiooo]).abs() < 1e-9);
+    assert!(Inst::output_entropy(&insts![iis]).abs() < 1e-9);
+    assert!((Inst::output_entropy(&insts![oioodo]) - 1.0).abs() < 1e-9);
+    assert!((Inst::output_entropy(&insts![oioioio]) - 2.0).abs() < 1e-9);
+}
+
+#[test]
+fn builder_push_number_via() {
+    let mut b = Builder::new(Acc::new());
+    b.push_number_via(Acc::from(12), Acc::from(150)).unwrap();
+    let (numbers, acc) = Inst::eval_numbers(b.insts());
+    assert_eq!((vec![Acc::from(150)], Acc::from(150)), (numbers, acc));
+    let mut expected = Inst::encode_number(Acc::new(), Acc::from(12));
+    expected.pop();
+    expected.extend(insts![siiiiiio]);
+    assert_eq!(expected, b.insts());
+
+    let err = b.push_number_via(Acc::from(17), Acc::from(5)).unwrap_err();
+    assert_eq!(
+        PivotUnreachable {
+            square: Acc::from(289),
+            n: Acc::from(5)
+        },
+        err
+    );
+    assert_eq!(Acc::from(150), b.acc());
+}
+
+#[test]
+fn ascii_encoding_table() {
+    let table = Inst::ascii_encoding_table();
+    for (c, program) in (' '..='~').zip(&table) {
+        assert_eq!(Some(c.to_string()), Inst::eval_string(program));
+        assert!(program.len() <= Inst::encode_number(Acc::new(), Acc::from(c as u32)).len());
+    }
+    assert_eq!(insts![iiisdsiiiiiiiio], table[usize::from(b'H' - b' ')]);
+}
+
+#[test]
+fn acc_try_apply() {
+    assert_eq!(Ok(Acc::new()), Acc::from(1).try_apply(Inst::D));
+    assert_eq!(
+        Err(ResetFired { from_256: true }),
+        Acc::from(255).try_apply(Inst::I)
+    );
+    assert_eq!(
+        Err(ResetFired { from_256: false }),
+        Acc::new().try_apply(Inst::D)
+    );
+    assert_eq!(
+        Err(ResetFired { from_256: true }),
+        Acc::from(16).try_apply(Inst::S)
+    );
+    // Wrapping to 0 is not a reset
+    assert_eq!(Ok(Acc::new()), Acc::from(65536).try_apply(Inst::S));
+    assert_eq!(Ok(Acc::from(3)), Acc::from(3).try_apply(Inst::O));
+}
+
+#[test]
+fn compare_encoders_report() {
+    let report = crate::compare_encoders_report(0..=20, 0..=40);
+    assert_eq!(21 * 41, report.pairs);
+    assert_eq!(report.pairs, report.matched + report.longer);
+
+    let mut matched = 0;
+    for acc in 0..=20 {
+        for n in 0..=40 {
+            if heuristic_is_optimal(Acc::from(acc), Acc::from(n), 16).is_ok() {
+                matched += 1;
+            }
+        }
+    }
+    assert_eq!(matched, report.matched);
+    assert_eq!(
+        format!(
+            "861 pairs: {matched} optimal, {} longer by at most {}",
+            report.longer, report.max_excess
+        ),
+        report.to_string()
+    );
+}
+
+#[test]
+fn parse_annotated() {
+    let (insts, comments) = Inst::parse_annotated("iis # reach four\nso");
+    assert_eq!(insts![iis__so], insts);
+    assert_eq!(
+        vec![Comment {
+            index: 4,
+            text: " reach four".to_owned()
+        }],
+        comments
+    );
+    assert_eq!(Inst::parse("iis \nso"), insts);
+
+    let (insts, comments) = Inst::parse_annotated("#a\n#b\nio#c");
+    assert_eq!(insts![__io], insts);
+    let positions = comments
+        .iter()
+        .map(|c| (c.index, &*c.text))
+        .collect::<Vec<_>>();
+    assert_eq!(vec![(0, "a"), (1, "b"), (4, "c")], positions);
+}
@@ -0,0 +1,129 @@
+// Copyright (C) 2022 Andrew Archibald
+//
+// deadfish is free software: you can redistribute it and/or modify it under the
+// terms of the GNU Lesser General Public License as published by the Free
+// Software Foundation, either version 3 of the License, or (at your option) any
+// later version. You should have received a copy of the GNU Lesser General
+// Public License along with deadfish. If not, see http://www.gnu.org/licenses/.
+
+use crate::{Acc, Accumulator, Builder, Inst, Width};
+
+/// An accumulator with a width fixed at compile time, as opposed to [`Acc`]
+/// with a runtime [`Width`]. The normalization constants are computed for
+/// each width, so arithmetic is as cheap as for [`Acc`].
+#[repr(transparent)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Value<const BITS: u32 = 32>(u32);
+
+/// An accumulator of the reference implementation's 32-bit `int`.
+pub type Value32 = Value<32>;
+/// An accumulator of a 16-bit `int`.
+pub type Value16 = Value<16>;
+
+impl<const BITS: u32> Value<BITS> {
+    /// The width of the accumulator. It's checked when evaluated, so a width
+    /// outside of 9 to 32 bits only fails to compile once `WIDTH` is used for
+    /// that `BITS`, e.g., by any arithmetic, not when naming `Value<BITS>`.
+    pub const WIDTH: Width = match Width::new(BITS) {
+        Some(width) => width,
+        None => panic!("width must be 9 to 32 bits"),
+    };
+
+    /// Create a new accumulator at zero.
+    #[must_use]
+    #[inline]
+    pub const fn new() -> Self {
+        Value(0)
+    }
+
+    #[must_use]
+    #[inline]
+    pub const fn value(self) -> u32 {
+        self.0
+    }
+
+    /// Compute the operation on the accumulator.
+    #[must_use]
+    #[inline]
+    pub const fn apply(self, inst: Inst) -> Self {
+        match inst {
+            Inst::I => self.increment(),
+            Inst::D => self.decrement(),
+            Inst::S => self.square(),
+            _ => self,
+        }
+    }
+
+    #[must_use]
+    #[inline]
+    pub const fn increment(self) -> Self {
+        Value(Self::WIDTH.normalize(self.0.wrapping_add(1)))
+    }
+
+    #[must_use]
+    #[inline]
+    pub const fn decrement(self) -> Self {
+        Value(Self::WIDTH.normalize(self.0.wrapping_sub(1)))
+    }
+
+    #[must_use]
+    #[inline]
+    pub const fn square(self) -> Self {
+        Value(Self::WIDTH.normalize(self.0.wrapping_mul(self.0)))
+    }
+
+    /// Converts to an [`Acc`], for encoding with a [`Builder`]
+    /// of [`Value::WIDTH`].
+    #[must_use]
+    #[inline]
+    pub const fn to_acc(self) -> Acc {
+        Acc::from_raw(self.0)
+    }
+
+    /// Encodes `n` from this value with the heuristic, with arithmetic at
+    /// [`Value::WIDTH`].
+    #[must_use]
+    pub fn encode_number(self, n: Self) -> Vec<Inst> {
+        let mut b = Builder::from_accumulator(self);
+        b.push_number(n.to_acc());
+        b.into_insts()
+    }
+}
+
+impl<const BITS: u32> Accumulator for Value<BITS> {
+    #[inline]
+    fn width() -> Width {
+        Self::WIDTH
+    }
+
+    #[inline]
+    fn to_acc(self) -> Acc {
+        Value::to_acc(self)
+    }
+
+    #[inline]
+    fn from_acc(acc: Acc) -> Self {
+        Self::from(acc.value())
+    }
+}
+
+impl<const BITS: u32> const Default for Value<BITS> {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const BITS: u32> const From<u32> for Value<BITS> {
+    #[inline]
+    fn from(n: u32) -> Self {
+        Value(Self::WIDTH.normalize(n))
+    }
+}
+
+impl<const BITS: u32> const From<Value<BITS>> for u32 {
+    #[inline]
+    fn from(v: Value<BITS>) -> Self {
+        v.0
+    }
+}